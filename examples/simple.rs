@@ -12,16 +12,29 @@ pub async fn main() -> color_eyre::Result<()> {
                     Ok(tokio_tungstenite::tungstenite::Message::Text(text)) => {
                         let res: Result<wire::TimestampedEvent<String>, String> =
                             Ok(wire::TimestampedEvent::new(text.to_string()));
-                        #[cfg(feature = "out-json")]
-                        let text = serde_json::to_string(&res).expect("request is always a string");
-                        #[cfg(feature = "out-ron")]
-                        let text = ron::to_string(&res).expect("request is always a string");
+                        let text = wire_cli::Format::default_out()
+                            .serialize(&res)
+                            .expect("request is always a string");
                         // Echo the message back
                         stream
                             .send(tokio_tungstenite::tungstenite::Message::Text(text.into()))
                             .await
                             .unwrap();
                     }
+                    Ok(tokio_tungstenite::tungstenite::Message::Binary(bytes)) => {
+                        let res: Result<wire::TimestampedEvent<String>, String> =
+                            Ok(wire::TimestampedEvent::new(format!("{bytes:02x?}")));
+                        let bytes = wire_cli::Format::default_out()
+                            .to_bytes(&res)
+                            .expect("request is always a string");
+                        // Echo the message back
+                        stream
+                            .send(tokio_tungstenite::tungstenite::Message::Binary(
+                                bytes.into(),
+                            ))
+                            .await
+                            .unwrap();
+                    }
                     Err(_) => {}
                     _ => {}
                 }
@@ -31,7 +44,8 @@ pub async fn main() -> color_eyre::Result<()> {
 
     let client: wire_cli::Client<String, String, String> =
         wire_cli::Client::new(wire_cli::ClientCfg {
-            url: format!("ws://{url}").into(),
+            url: format!("ws://{url}"),
+            ..Default::default()
         });
     let result = client.start().await;
     server_task.abort(); // Stop the server after the client finishes
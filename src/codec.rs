@@ -0,0 +1,173 @@
+use crate::{Format, Res};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Converts actions into frames sent to the server and frames received from the server
+/// into responses.
+///
+/// Implement this to speak a custom envelope format and pass it to [`crate::Client::codec`].
+pub trait Codec<Action, Event, Err>: Send + Sync + 'static {
+    /// Encodes an action into a frame.
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message>;
+
+    /// Decodes a data frame into a response.
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>>;
+
+    /// Returns the built-in format of the frames, used to pretty-print them in the detail
+    /// pane. Custom formats are shown as decoded instead.
+    fn format(&self) -> Option<Format> {
+        None
+    }
+}
+
+/// A codec speaking JSON over text frames.
+#[cfg(any(feature = "in-json", feature = "out-json"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonCodec;
+
+#[cfg(any(feature = "in-json", feature = "out-json"))]
+impl<Action, Event, Err> Codec<Action, Event, Err> for JsonCodec
+where
+    Action: serde::Serialize,
+    Event: serde::de::DeserializeOwned,
+    Err: serde::de::DeserializeOwned,
+{
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message> {
+        Ok(Message::Text(serde_json::to_string(action)?.into()))
+    }
+
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>> {
+        match msg {
+            Message::Text(text) => Ok(serde_json::from_str(text)?),
+            Message::Binary(bytes) => Ok(serde_json::from_slice(bytes)?),
+            _ => Err(color_eyre::eyre::eyre!("not a data frame: {msg:?}")),
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(Format::Json)
+    }
+}
+
+/// A codec speaking RON over text frames.
+#[cfg(any(feature = "in-ron", feature = "out-ron"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RonCodec;
+
+#[cfg(any(feature = "in-ron", feature = "out-ron"))]
+impl<Action, Event, Err> Codec<Action, Event, Err> for RonCodec
+where
+    Action: serde::Serialize,
+    Event: serde::de::DeserializeOwned,
+    Err: serde::de::DeserializeOwned,
+{
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message> {
+        Ok(Message::Text(ron::to_string(action)?.into()))
+    }
+
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>> {
+        match msg {
+            Message::Text(text) => Ok(ron::from_str(text)?),
+            Message::Binary(bytes) => Ok(ron::de::from_bytes(bytes)?),
+            _ => Err(color_eyre::eyre::eyre!("not a data frame: {msg:?}")),
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(Format::Ron)
+    }
+}
+
+/// A codec speaking MessagePack over binary frames.
+#[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MsgPackCodec;
+
+#[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+impl<Action, Event, Err> Codec<Action, Event, Err> for MsgPackCodec
+where
+    Action: serde::Serialize,
+    Event: serde::de::DeserializeOwned,
+    Err: serde::de::DeserializeOwned,
+{
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message> {
+        Ok(Message::Binary(rmp_serde::to_vec_named(action)?.into()))
+    }
+
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>> {
+        match msg {
+            Message::Binary(bytes) => Ok(rmp_serde::from_slice(bytes)?),
+            _ => Err(color_eyre::eyre::eyre!("not a binary frame: {msg:?}")),
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(Format::MsgPack)
+    }
+}
+
+/// A codec speaking CBOR over binary frames.
+#[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CborCodec;
+
+#[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+impl<Action, Event, Err> Codec<Action, Event, Err> for CborCodec
+where
+    Action: serde::Serialize,
+    Event: serde::de::DeserializeOwned,
+    Err: serde::de::DeserializeOwned,
+{
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(action, &mut bytes)?;
+        Ok(Message::Binary(bytes.into()))
+    }
+
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>> {
+        match msg {
+            Message::Binary(bytes) => Ok(ciborium::from_reader(&bytes[..])?),
+            _ => Err(color_eyre::eyre::eyre!("not a binary frame: {msg:?}")),
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(Format::Cbor)
+    }
+}
+
+impl<Action, Event, Err> Codec<Action, Event, Err> for Format
+where
+    Action: serde::Serialize,
+    Event: serde::de::DeserializeOwned,
+    Err: serde::de::DeserializeOwned,
+{
+    fn encode(&self, action: &Action) -> color_eyre::Result<Message> {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Codec::<Action, Event, Err>::encode(&JsonCodec, action),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Codec::<Action, Event, Err>::encode(&RonCodec, action),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => Codec::<Action, Event, Err>::encode(&MsgPackCodec, action),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => Codec::<Action, Event, Err>::encode(&CborCodec, action),
+        }
+    }
+
+    fn decode(&self, msg: &Message) -> color_eyre::Result<Res<Event, Err>> {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Codec::<Action, Event, Err>::decode(&JsonCodec, msg),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Codec::<Action, Event, Err>::decode(&RonCodec, msg),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => Codec::<Action, Event, Err>::decode(&MsgPackCodec, msg),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => Codec::<Action, Event, Err>::decode(&CborCodec, msg),
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(*self)
+    }
+}
@@ -0,0 +1,732 @@
+use crate::record::{MessageKind, MessageRecord, RawFrame};
+use crate::{ClientCfg, ClientError, Codec, Correlate, Format};
+use futures::{SinkExt, StreamExt};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// How long the server gets to answer the close frame sent on shutdown.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A request from the UI to send something to the server.
+#[derive(Debug)]
+pub(crate) enum Outgoing<Action> {
+    /// An action, encoded with the codec.
+    Action(Action),
+    /// A frame sent as is, shown as the record once sent.
+    Frame(Message, Box<MessageRecord>),
+}
+
+impl<Action> Outgoing<Action>
+where
+    Action: serde::de::DeserializeOwned + std::fmt::Debug + 'static,
+{
+    /// Parses a typed request in the format.
+    ///
+    /// With `verbatim` set, the request is sent as a text frame exactly as written instead
+    /// of being encoded again, see [`ClientCfg::verbatim_actions`].
+    pub fn parse(format: Format, verbatim: bool, input: &str) -> color_eyre::Result<Self> {
+        let action = format.deserialize::<Action>(input)?;
+        if !verbatim {
+            return Ok(Outgoing::Action(action));
+        }
+
+        let msg = Message::Text(input.into());
+        let record = MessageRecord::sent(&action, RawFrame::from_message(&msg));
+        Ok(Outgoing::Frame(msg, Box::new(record)))
+    }
+}
+
+impl<Action: std::fmt::Debug> std::fmt::Display for Outgoing<Action> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outgoing::Action(action) => write!(f, "{action:?}"),
+            Outgoing::Frame(_, record) => f.write_str(&record.text),
+        }
+    }
+}
+
+/// Information about an established connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ConnInfo {
+    /// The url the client connected to.
+    pub url: String,
+    /// The subprotocol negotiated with the server, if any.
+    pub subprotocol: Option<String>,
+    /// The headers of the handshake response.
+    pub headers: Vec<(String, String)>,
+}
+
+impl ConnInfo {
+    fn new(url: &str, res: &tokio_tungstenite::tungstenite::handshake::client::Response) -> Self {
+        let headers = res
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let subprotocol = res
+            .headers()
+            .get(SEC_WEBSOCKET_PROTOCOL)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+        Self {
+            url: url.to_string(),
+            subprotocol,
+            headers,
+        }
+    }
+}
+
+/// The state of the connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum LinkState {
+    /// The first connection attempt is running.
+    #[default]
+    Connecting,
+    Connected,
+    /// The connection dropped or failed, another attempt follows.
+    Reconnecting,
+    /// The client stopped connecting.
+    Closed,
+}
+
+impl LinkState {
+    /// Returns the label shown in the help bar.
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkState::Connecting => "connecting",
+            LinkState::Connected => "connected",
+            LinkState::Reconnecting => "reconnecting",
+            LinkState::Closed => "closed",
+        }
+    }
+}
+
+/// How alive the connection is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Health {
+    pub state: LinkState,
+    /// The round-trip time of the last answered ping.
+    pub rtt: Option<Duration>,
+    /// When the last frame of any kind was received.
+    pub last_frame: Option<tokio::time::Instant>,
+}
+
+impl std::fmt::Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.state.label())?;
+        if self.state != LinkState::Connected {
+            return Ok(());
+        }
+        if let Some(rtt) = self.rtt {
+            write!(f, " | rtt {} ms", rtt.as_millis())?;
+        }
+        if let Some(last_frame) = self.last_frame {
+            write!(f, " | last frame {}s ago", last_frame.elapsed().as_secs())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the system message reporting a close frame received from the server.
+fn close_record(frame: Option<&CloseFrame>) -> MessageRecord {
+    let Some(frame) = frame else {
+        return MessageRecord::system("server closed the connection without a code");
+    };
+
+    let code = u16::from(frame.code);
+    // the name of the code, e.g. `Away` for 1001
+    let name = format!("{:?}", frame.code);
+    let reason = frame.reason.as_str();
+    let mut text = format!("server closed the connection with code {code} ({name})");
+    if !reason.is_empty() {
+        text.push_str(&format!(": {reason}"));
+    }
+
+    let mut record = MessageRecord::system(text);
+    // laid out like `{:#?}` so it can be browsed like a decoded event
+    record.decoded = Some(format!(
+        "Close {{\n    code: {code},\n    name: {name:?},\n    reason: {reason:?},\n}}"
+    ));
+    record.variant = Some("Close".to_string());
+    record
+}
+
+/// Why a connection session ended.
+enum Closed {
+    /// The client is shutting down, so no reconnect should happen.
+    Shutdown,
+    /// The socket went away and the supervisor should reconnect.
+    Disconnected(String),
+}
+
+/// Computes reconnect delays with exponential backoff and jitter.
+struct Backoff {
+    delay: Duration,
+    max_delay: Duration,
+    factor: u32,
+    jitter: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(cfg: &ClientCfg) -> Self {
+        Self {
+            delay: cfg.reconnect_delay,
+            max_delay: cfg.reconnect_max_delay,
+            factor: cfg.reconnect_factor,
+            jitter: cfg.reconnect_jitter,
+            current: cfg.reconnect_delay,
+        }
+    }
+
+    /// Returns the delay before the next attempt and grows the backoff.
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.current.min(self.max_delay);
+        self.current = delay.saturating_mul(self.factor).min(self.max_delay);
+
+        delay + random_fraction(self.jitter)
+    }
+
+    /// Resets the backoff after a successful connection.
+    fn reset(&mut self) {
+        self.current = self.delay;
+    }
+}
+
+/// Returns a random duration in `[0, max)`.
+fn random_fraction(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+
+    // `RandomState` is seeded randomly per instance, which is enough for jitter
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    let nanos = random % max.as_nanos().min(u64::MAX as u128) as u64;
+    Duration::from_nanos(nanos)
+}
+
+/// Builds the handshake request from the url, the extra headers and the subprotocols.
+pub(crate) fn handshake_request(
+    cfg: &ClientCfg,
+) -> color_eyre::Result<tokio_tungstenite::tungstenite::handshake::client::Request> {
+    let mut req = cfg.url.as_str().into_client_request()?;
+    for (name, value) in &cfg.headers {
+        req.headers_mut().append(name, value.clone());
+    }
+    if !cfg.subprotocols.is_empty() {
+        let protocols = HeaderValue::from_str(&cfg.subprotocols.join(", "))?;
+        req.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, protocols);
+    }
+
+    Ok(req)
+}
+
+/// The TLS settings for `wss://` urls, built once so certificates are read at startup
+/// instead of on every reconnect.
+#[derive(Clone, Default)]
+pub(crate) struct Connector(#[cfg(feature = "tls")] Option<tokio_tungstenite::Connector>);
+
+impl Connector {
+    /// Builds the connector from the config, failing on unreadable certificates.
+    pub fn new(cfg: &ClientCfg) -> color_eyre::Result<Self> {
+        #[cfg(feature = "tls")]
+        return Ok(Self(Some(crate::tls::connector(cfg)?)));
+        #[cfg(not(feature = "tls"))]
+        {
+            let _ = cfg;
+            Ok(Self())
+        }
+    }
+
+    /// Opens a connection with the handshake request.
+    async fn connect(
+        &self,
+        req: tokio_tungstenite::tungstenite::handshake::client::Request,
+    ) -> color_eyre::Result<(
+        WsStream,
+        tokio_tungstenite::tungstenite::handshake::client::Response,
+    )> {
+        #[cfg(feature = "tls")]
+        let conn =
+            tokio_tungstenite::connect_async_tls_with_config(req, None, false, self.0.clone())
+                .await?;
+        #[cfg(not(feature = "tls"))]
+        let conn = tokio_tungstenite::connect_async(req).await?;
+
+        Ok(conn)
+    }
+}
+
+/// Where and how the connection task connects.
+struct Endpoint {
+    cfg: ClientCfg,
+    connector: Connector,
+}
+
+impl Endpoint {
+    /// Opens a connection to the server.
+    async fn connect(
+        &self,
+    ) -> color_eyre::Result<(
+        WsStream,
+        tokio_tungstenite::tungstenite::handshake::client::Response,
+    )> {
+        self.connector.connect(handshake_request(&self.cfg)?).await
+    }
+}
+
+/// The task keeping the connection alive and the channels to it.
+pub(crate) struct Link<Action> {
+    /// Taken once the task stopped and its outcome was reported.
+    task: Option<tokio::task::JoinHandle<()>>,
+    pub req_tx: Sender<Outgoing<Action>>,
+    pub msg_rx: Receiver<MessageRecord>,
+    pub info_rx: watch::Receiver<Option<ConnInfo>>,
+    pub health_rx: watch::Receiver<Health>,
+}
+
+impl<Action> Link<Action>
+where
+    Action: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    /// Spawns the connection task.
+    pub fn spawn<Event, Err>(
+        cfg: &ClientCfg,
+        connector: Connector,
+        codec: Arc<dyn Codec<Action, Event, Err>>,
+        correlate: Option<Arc<dyn Correlate<Action, Event, Err>>>,
+    ) -> Self
+    where
+        Event: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+        Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let (msg_tx, msg_rx) = tokio::sync::mpsc::channel(100);
+        let (req_tx, req_rx) = tokio::sync::mpsc::channel(100);
+        let (info_tx, info_rx) = watch::channel(None);
+        let (health_tx, health_rx) = watch::channel(Health::default());
+        let endpoint = Endpoint {
+            cfg: cfg.clone(),
+            connector,
+        };
+        let task = tokio::spawn(supervise(
+            endpoint, codec, correlate, req_rx, msg_tx, info_tx, health_tx,
+        ));
+
+        Self {
+            task: Some(task),
+            req_tx,
+            msg_rx,
+            info_rx,
+            health_rx,
+        }
+    }
+
+    /// Returns why the connection task stopped, once.
+    pub async fn stopped(&mut self) -> Option<ClientError> {
+        let task = self.task.take_if(|task| task.is_finished())?;
+        match task.await {
+            Err(err) if err.is_panic() => {
+                let panic = err.into_panic();
+                let msg = panic
+                    .downcast_ref::<&str>()
+                    .map(|msg| msg.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Some(ClientError::ConnectionPanicked(msg))
+            }
+            _ => Some(ClientError::ConnectionStopped),
+        }
+    }
+
+    /// Closes the connection, aborting the task if that takes too long.
+    pub async fn shut_down(self) {
+        let Some(mut task) = self.task else {
+            return;
+        };
+        // the supervisor sends a close frame once the request channel is dropped
+        drop(self.req_tx);
+
+        // the session may be busy with a request before it notices the closed channel
+        if tokio::time::timeout(CLOSE_TIMEOUT * 2, &mut task)
+            .await
+            .is_err()
+        {
+            task.abort();
+        }
+    }
+}
+
+/// Keeps a connection to the server alive, reconnecting with backoff whenever it drops.
+///
+/// Returns once the UI side of the request or message channels is closed.
+async fn supervise<Action, Event, Err>(
+    endpoint: Endpoint,
+    codec: Arc<dyn Codec<Action, Event, Err>>,
+    correlate: Option<Arc<dyn Correlate<Action, Event, Err>>>,
+    mut req_rx: Receiver<Outgoing<Action>>,
+    msg_tx: Sender<MessageRecord>,
+    info_tx: watch::Sender<Option<ConnInfo>>,
+    health_tx: watch::Sender<Health>,
+) where
+    Action: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Event: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    let _closed = ClosedGuard(&health_tx);
+    let cfg = &endpoint.cfg;
+    let mut backoff = Backoff::new(cfg);
+    loop {
+        match endpoint.connect().await {
+            Ok((stream, res)) => {
+                backoff.reset();
+                let info = ConnInfo::new(&cfg.url, &res);
+                let msg = match &info.subprotocol {
+                    Some(subprotocol) => {
+                        format!("connected to {} using {subprotocol}", cfg.url)
+                    }
+                    None => format!("connected to {}", cfg.url),
+                };
+                info_tx.send_replace(Some(info));
+                health_tx.send_replace(Health {
+                    state: LinkState::Connected,
+                    rtt: None,
+                    last_frame: None,
+                });
+                if msg_tx.send(MessageRecord::system(msg)).await.is_err() {
+                    return;
+                }
+
+                match session(
+                    stream,
+                    cfg,
+                    &*codec,
+                    correlate.as_deref(),
+                    &mut req_rx,
+                    &msg_tx,
+                    &health_tx,
+                )
+                .await
+                {
+                    Closed::Shutdown => return,
+                    Closed::Disconnected(reason) => {
+                        info_tx.send_replace(None);
+                        if msg_tx
+                            .send(MessageRecord::system(format!("disconnected: {reason}")))
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                if msg_tx
+                    .send(MessageRecord::system(format!(
+                        "failed to connect to {}: {err}",
+                        cfg.url
+                    )))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+
+        health_tx.send_modify(|health| health.state = LinkState::Reconnecting);
+        let delay = backoff.next_delay();
+        if msg_tx
+            .send(MessageRecord::system(format!(
+                "reconnecting in {:.1}s",
+                delay.as_secs_f64()
+            )))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        // keep draining requests while waiting so the UI never blocks on a full channel
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                req = req_rx.recv() => match req {
+                    Some(req) => {
+                        if msg_tx.send(MessageRecord::system(format!("not connected, dropped request: {req}")))
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    None => return,
+                },
+            }
+        }
+    }
+}
+
+/// Sends a close frame with the configured code and waits for the server to answer it.
+async fn close(
+    cfg: &ClientCfg,
+    ws_tx: &mut futures::stream::SplitSink<WsStream, Message>,
+    ws_rx: &mut futures::stream::SplitStream<WsStream>,
+) {
+    let code = match CloseCode::from(cfg.close_code) {
+        code if code.is_allowed() => code,
+        _ => CloseCode::Normal,
+    };
+    let frame = CloseFrame {
+        code,
+        reason: cfg.close_reason.clone().into(),
+    };
+    if ws_tx.send(Message::Close(Some(frame))).await.is_err() {
+        return;
+    }
+
+    let _ = tokio::time::timeout(CLOSE_TIMEOUT, async {
+        while let Some(Ok(msg)) = ws_rx.next().await {
+            if msg.is_close() {
+                break;
+            }
+        }
+    })
+    .await;
+}
+
+/// Marks the connection closed when the supervisor stops.
+struct ClosedGuard<'a>(&'a watch::Sender<Health>);
+
+impl Drop for ClosedGuard<'_> {
+    fn drop(&mut self) {
+        self.0
+            .send_modify(|health| health.state = LinkState::Closed);
+    }
+}
+
+/// Pumps messages between the socket and the UI channels until the connection ends.
+async fn session<Action, Event, Err>(
+    stream: WsStream,
+    cfg: &ClientCfg,
+    codec: &dyn Codec<Action, Event, Err>,
+    correlate: Option<&dyn Correlate<Action, Event, Err>>,
+    req_rx: &mut Receiver<Outgoing<Action>>,
+    msg_tx: &Sender<MessageRecord>,
+    health_tx: &watch::Sender<Health>,
+) -> Closed
+where
+    Action: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Event: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    let (mut ws_tx, mut ws_rx) = stream.split();
+    let ping_interval = cfg.ping_interval.filter(|interval| !interval.is_zero());
+    // only polled if pinging is enabled
+    let mut ping = tokio::time::interval(ping_interval.unwrap_or(Duration::from_secs(60)));
+    ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // the first tick completes immediately
+    ping.tick().await;
+    // without pings a quiet but healthy server sends no frames at all
+    let idle_timeout = cfg.idle_timeout.filter(|_| ping_interval.is_some());
+    let mut ping_sent: Option<(tokio::time::Instant, Vec<u8>)> = None;
+    let mut pings = 0u64;
+    let mut last_frame = tokio::time::Instant::now();
+    loop {
+        let idle_at = idle_timeout.and_then(|timeout| last_frame.checked_add(timeout));
+        let idle = async move {
+            match idle_at {
+                Some(idle_at) => tokio::time::sleep_until(idle_at).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            // read ws messages
+            msg = ws_rx.next() => {
+                if let Some(Ok(_)) = &msg {
+                    last_frame = tokio::time::Instant::now();
+                    health_tx.send_modify(|health| health.last_frame = Some(last_frame));
+                }
+                match msg {
+                    Some(Ok(
+                        msg @ (Message::Text(_)
+                        | Message::Binary(_)),
+                    )) => {
+                        let raw = RawFrame::from_message(&msg);
+                        let record = match codec.decode(&msg) {
+                            Ok(res) => MessageRecord::received(&res, raw).correlation(
+                                correlate.and_then(|correlate| correlate.response_id(&res)),
+                            ),
+                            Err(err) => MessageRecord::new(
+                                MessageKind::Error,
+                                format!("failed to decode frame: {err}"),
+                            )
+                            .raw(raw),
+                        };
+                        if msg_tx.send(record).await.is_err() {
+                            return Closed::Shutdown;
+                        };
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        if msg_tx.send(close_record(frame.as_ref())).await.is_err() {
+                            return Closed::Shutdown;
+                        }
+                        return Closed::Disconnected("closed by server".to_string());
+                    }
+                    Some(Ok(Message::Pong(payload))) => {
+                        if let Some((sent_at, _)) = ping_sent
+                            .take_if(|(_, sent)| sent.as_slice() == payload.as_ref())
+                        {
+                            let rtt = sent_at.elapsed();
+                            health_tx.send_modify(|health| health.rtt = Some(rtt));
+                        } else {
+                            // most likely the answer to a raw ping
+                            let record = MessageRecord::system(format!(
+                                "received pong: {}",
+                                String::from_utf8_lossy(&payload).escape_debug()
+                            ));
+                            if msg_tx.send(record).await.is_err() {
+                                return Closed::Shutdown;
+                            }
+                        }
+                    }
+                    // pings are answered by tungstenite itself
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Closed::Disconnected(err.to_string()),
+                    None => return Closed::Disconnected("connection closed".to_string()),
+                }
+            },
+            _ = ping.tick(), if ping_interval.is_some() => {
+                pings += 1;
+                let payload = pings.to_be_bytes().to_vec();
+                if let Err(err) = ws_tx.send(Message::Ping(payload.clone().into())).await {
+                    return Closed::Disconnected(err.to_string());
+                }
+                ping_sent = Some((tokio::time::Instant::now(), payload));
+            }
+            _ = idle => {
+                return Closed::Disconnected(format!(
+                    "no frames for {:.1}s, considering the connection dead",
+                    last_frame.elapsed().as_secs_f64()
+                ));
+            }
+            // write ws messages
+            req = req_rx.recv() => {
+                let Some(req) = req else {
+                    close(cfg, &mut ws_tx, &mut ws_rx).await;
+                    return Closed::Shutdown;
+                };
+
+                let (msg, record) = match req {
+                    Outgoing::Action(req) => match codec.encode(&req) {
+                        Ok(msg) => {
+                            let record = MessageRecord::sent(&req, RawFrame::from_message(&msg))
+                                .correlation(
+                                    correlate.and_then(|correlate| correlate.request_id(&req)),
+                                );
+                            (msg, record)
+                        }
+                        Err(err) => {
+                            let record = MessageRecord::new(
+                                MessageKind::Error,
+                                format!("failed to encode {req:?}: {err}"),
+                            );
+                            if msg_tx.send(record).await.is_err() {
+                                return Closed::Shutdown;
+                            }
+                            continue;
+                        }
+                    },
+                    Outgoing::Frame(msg, record) => {
+                        let record = MessageRecord {
+                            kind: MessageKind::Sent,
+                            received_at: chrono::Utc::now(),
+                            instant: std::time::Instant::now(),
+                            latency_ms: None,
+                            response_to: None,
+                            ..*record
+                        };
+                        (msg, record)
+                    }
+                };
+                if let Err(err) = ws_tx.send(msg).await {
+                    return Closed::Disconnected(err.to_string());
+                }
+                if msg_tx.send(record).await.is_err() {
+                    return Closed::Shutdown;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    #[test]
+    fn verbatim_ron_actions_keep_their_names() {
+        let input = r#"Login(user: "x")"#;
+        let Ok(Outgoing::Frame(msg, record)) =
+            Outgoing::<ron::Value>::parse(Format::Ron, true, input)
+        else {
+            panic!("not sent verbatim");
+        };
+        assert_eq!(&msg.into_data()[..], input.as_bytes());
+        assert_eq!(record.kind, MessageKind::Sent);
+        assert_eq!(record.variant.as_deref(), Some("Login"));
+
+        // encoding the parsed value again drops the name
+        let Ok(Outgoing::Action(action)) = Outgoing::<ron::Value>::parse(Format::Ron, false, input)
+        else {
+            panic!("not parsed as an action");
+        };
+        assert_eq!(Format::Ron.serialize(&action).unwrap(), r#"{"user":"x"}"#);
+    }
+
+    #[test]
+    fn close_record_reads_like_debug() {
+        let frame = CloseFrame {
+            code: CloseCode::Away,
+            reason: "going \"down\"".into(),
+        };
+        let record = close_record(Some(&frame));
+        assert_eq!(
+            record.text,
+            r#"server closed the connection with code 1001 (Away): going "down""#
+        );
+        assert_eq!(record.variant.as_deref(), Some("Close"));
+
+        let tree = crate::tree::Tree::parse(record.decoded.as_deref().unwrap()).unwrap();
+        assert_eq!(tree.variant(), Some("Close"));
+        assert_eq!(tree.get(".code"), Some("1001"));
+        assert_eq!(tree.get(".name"), Some(r#""Away""#));
+        assert_eq!(tree.get(".reason"), Some(r#""going \"down\"""#));
+
+        let record = close_record(None);
+        assert_eq!(record.decoded, None);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::record::{MessageKind, MessageRecord};
+use crate::Res;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// The maximum number of sent actions remembered, answered or not.
+const MAX_REQUESTS: usize = 10_000;
+/// How long an answered action is remembered to link further responses to it.
+const ANSWERED_SECS: u64 = 60;
+/// The number of the latest latencies per action variant the percentiles are taken of.
+const MAX_SAMPLES: usize = 1000;
+
+/// Pulls correlation ids out of actions and responses, linking responses to the actions
+/// they answer.
+///
+/// Implement this or pass a pair of closures to [`crate::Client::correlate`].
+pub trait Correlate<Action, Event, Err>: Send + Sync + 'static {
+    /// Returns the id of an action, if it expects a response.
+    fn request_id(&self, action: &Action) -> Option<String>;
+
+    /// Returns the id of the action a response answers.
+    fn response_id(&self, res: &Res<Event, Err>) -> Option<String>;
+}
+
+impl<Action, Event, Err, Req, Resp> Correlate<Action, Event, Err> for (Req, Resp)
+where
+    Req: Fn(&Action) -> Option<String> + Send + Sync + 'static,
+    Resp: Fn(&Res<Event, Err>) -> Option<String> + Send + Sync + 'static,
+{
+    fn request_id(&self, action: &Action) -> Option<String> {
+        (self.0)(action)
+    }
+
+    fn response_id(&self, res: &Res<Event, Err>) -> Option<String> {
+        (self.1)(res)
+    }
+}
+
+/// A sent action responses are linked to.
+#[derive(Debug)]
+struct Request {
+    /// The index of the action in the message history.
+    idx: usize,
+    /// When the action was sent, on the monotonic clock so latencies survive clock changes.
+    sent_at: Instant,
+    variant: String,
+    answered: bool,
+}
+
+/// Links responses to their actions by correlation id and collects the latencies.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    requests: HashMap<String, Request>,
+    /// The ids of the requests with their indices, oldest first.
+    order: VecDeque<(usize, String)>,
+    /// The round-trip latencies by action variant.
+    latencies: BTreeMap<String, Latencies>,
+}
+
+/// The latest round-trip latencies of an action variant.
+#[derive(Debug, Default)]
+struct Latencies {
+    /// The number of latencies ever measured.
+    count: usize,
+    /// The latest latencies in milliseconds, oldest first.
+    samples: VecDeque<f64>,
+    /// The summary line, updated with every latency.
+    summary: String,
+}
+
+impl Latencies {
+    fn push(&mut self, variant: &str, latency: f64) {
+        self.count += 1;
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by(f64::total_cmp);
+        self.summary = format!(
+            "{variant}: {} responses, p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms",
+            self.count,
+            percentile(&sorted, 50.0),
+            percentile(&sorted, 95.0),
+            percentile(&sorted, 99.0)
+        );
+    }
+}
+
+impl Tracker {
+    /// Remembers a sent action, or links a response and sets its latency if it is the
+    /// first one to the action.
+    pub fn track(&mut self, msg: &mut MessageRecord, idx: usize) {
+        let Some(id) = &msg.correlation else {
+            return;
+        };
+
+        match msg.kind {
+            MessageKind::Sent => {
+                let request = Request {
+                    idx,
+                    sent_at: msg.instant,
+                    variant: msg.variant.clone().unwrap_or_else(|| "other".to_string()),
+                    answered: false,
+                };
+                self.requests.insert(id.clone(), request);
+                self.order.push_back((idx, id.clone()));
+                self.evict(msg.instant);
+            }
+            MessageKind::Received | MessageKind::Error => {
+                let Some(request) = self.requests.get_mut(id) else {
+                    return;
+                };
+                msg.response_to = Some(request.idx);
+                if !request.answered {
+                    request.answered = true;
+                    let latency = msg
+                        .instant
+                        .saturating_duration_since(request.sent_at)
+                        .as_secs_f64()
+                        * 1000.0;
+                    msg.latency_ms = Some(latency);
+                    self.latencies
+                        .entry(request.variant.clone())
+                        .or_default()
+                        .push(&request.variant, latency);
+                }
+            }
+            MessageKind::System => {}
+        }
+    }
+
+    /// Forgets the oldest requests past the limit and those answered a while ago.
+    fn evict(&mut self, now: Instant) {
+        while let Some((idx, id)) = self.order.front() {
+            let request = self.requests.get(id).filter(|request| request.idx == *idx);
+            let expired = request.is_none_or(|request| {
+                request.answered
+                    && now.saturating_duration_since(request.sent_at)
+                        > Duration::from_secs(ANSWERED_SECS)
+            });
+            if !expired && self.order.len() <= MAX_REQUESTS {
+                break;
+            }
+
+            // an id sent again replaces the request, which is then only removed with it
+            if request.is_some() {
+                self.requests.remove(id);
+            }
+            self.order.pop_front();
+        }
+    }
+
+    /// Returns the latency percentiles per action variant, one line each.
+    ///
+    /// The percentiles are taken of the latest latencies of each variant.
+    pub fn summary(&self) -> Vec<&str> {
+        self.latencies
+            .values()
+            .map(|latencies| latencies.summary.as_str())
+            .collect()
+    }
+}
+
+/// Returns the nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The time the seconds of [`msg`] count from.
+    static START: std::sync::LazyLock<Instant> = std::sync::LazyLock::new(Instant::now);
+
+    fn msg(kind: MessageKind, id: &str, secs: u64) -> MessageRecord {
+        let mut msg = MessageRecord::new(kind, "").correlation(Some(id.to_string()));
+        msg.instant = *START + Duration::from_secs(secs);
+        msg
+    }
+
+    #[test]
+    fn latency_ignores_the_wall_clock() {
+        let mut tracker = Tracker::default();
+        let mut sent = msg(MessageKind::Sent, "a", 0);
+        sent.received_at = chrono::DateTime::UNIX_EPOCH + chrono::TimeDelta::seconds(10);
+        tracker.track(&mut sent, 0);
+
+        // the wall clock stepped back in between
+        let mut res = msg(MessageKind::Received, "a", 2);
+        res.received_at = chrono::DateTime::UNIX_EPOCH;
+        tracker.track(&mut res, 1);
+        assert_eq!(res.latency_ms, Some(2000.0));
+    }
+
+    #[test]
+    fn links_responses_and_measures_the_first() {
+        let mut tracker = Tracker::default();
+        tracker.track(&mut msg(MessageKind::Sent, "a", 0), 0);
+        let mut first = msg(MessageKind::Received, "a", 1);
+        tracker.track(&mut first, 1);
+        let mut second = msg(MessageKind::Error, "a", 2);
+        tracker.track(&mut second, 2);
+
+        assert_eq!(
+            (first.response_to, first.latency_ms),
+            (Some(0), Some(1000.0))
+        );
+        assert_eq!((second.response_to, second.latency_ms), (Some(0), None));
+        assert_eq!(tracker.summary().len(), 1);
+    }
+
+    #[test]
+    fn keeps_the_latest_latencies() {
+        let mut tracker = Tracker::default();
+        for idx in 0..MAX_SAMPLES + 10 {
+            let id = idx.to_string();
+            let mut sent = msg(MessageKind::Sent, &id, 0);
+            sent.variant = Some("Ping".to_string());
+            tracker.track(&mut sent, idx * 2);
+            tracker.track(&mut msg(MessageKind::Received, &id, 1), idx * 2 + 1);
+        }
+
+        assert_eq!(tracker.latencies["Ping"].samples.len(), MAX_SAMPLES);
+        assert_eq!(
+            tracker.summary(),
+            ["Ping: 1010 responses, p50 1000.0 ms, p95 1000.0 ms, p99 1000.0 ms"]
+        );
+    }
+
+    #[test]
+    fn forgets_answered_requests_after_a_while() {
+        let mut tracker = Tracker::default();
+        tracker.track(&mut msg(MessageKind::Sent, "a", 0), 0);
+        tracker.track(&mut msg(MessageKind::Received, "a", 1), 1);
+        tracker.track(&mut msg(MessageKind::Sent, "b", 2), 2);
+        // sent again while the old one is still remembered
+        tracker.track(&mut msg(MessageKind::Sent, "b", 3), 3);
+        assert_eq!(tracker.requests.len(), 2);
+
+        tracker.track(&mut msg(MessageKind::Sent, "c", ANSWERED_SECS + 1), 4);
+        assert!(!tracker.requests.contains_key("a"));
+        assert_eq!(tracker.requests["b"].idx, 3);
+
+        let mut late = msg(MessageKind::Received, "a", ANSWERED_SECS + 2);
+        tracker.track(&mut late, 5);
+        assert_eq!(late.response_to, None);
+    }
+
+    #[test]
+    fn caps_unanswered_requests() {
+        let mut tracker = Tracker::default();
+        for idx in 0..MAX_REQUESTS + 10 {
+            tracker.track(&mut msg(MessageKind::Sent, &idx.to_string(), 0), idx);
+        }
+        assert_eq!(tracker.requests.len(), MAX_REQUESTS);
+        assert!(!tracker.requests.contains_key("9"));
+        assert!(tracker.requests.contains_key("10"));
+    }
+}
@@ -0,0 +1,260 @@
+use crate::record::{MessageRecord, RawFrame};
+use crate::tree::Tree;
+use crate::Format;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+
+/// The number of bytes per line of a hex dump.
+const DUMP_WIDTH: usize = 16;
+
+/// The pane showing the selected message in full, scrolled independently of the list.
+///
+/// The payload can also be browsed as a tree of collapsible nodes.
+#[derive(Debug, Default)]
+pub(crate) struct Detail {
+    show_tree: bool,
+    /// The tree of the selected message once parsed, `Some(None)` if it is not a tree.
+    tree: Option<Option<Tree>>,
+    /// The index of the last rendered message with its laid out lines.
+    text: Option<(usize, Vec<Line<'static>>)>,
+    /// The first visible line.
+    scroll: u16,
+    /// The number of lines of the last rendered message.
+    len: u16,
+    /// The number of visible lines in the last render.
+    height: u16,
+}
+
+impl Detail {
+    /// Scrolls back to the top, e.g. after another message was selected.
+    pub fn reset(&mut self) {
+        self.scroll = 0;
+        self.tree = None;
+        self.text = None;
+    }
+
+    /// Switches between the text and the tree of the payload.
+    pub fn toggle_tree(&mut self) {
+        self.show_tree = !self.show_tree;
+    }
+
+    /// Scrolls down by some lines, or focuses a later node of the tree.
+    pub fn down(&mut self, lines: u16) {
+        match self.tree_mut() {
+            Some(tree) => tree.down(lines.into()),
+            None => {
+                self.scroll = self
+                    .scroll
+                    .saturating_add(lines)
+                    .min(self.len.saturating_sub(1))
+            }
+        }
+    }
+
+    /// Scrolls up by some lines, or focuses an earlier node of the tree.
+    pub fn up(&mut self, lines: u16) {
+        match self.tree_mut() {
+            Some(tree) => tree.up(lines.into()),
+            None => self.scroll = self.scroll.saturating_sub(lines),
+        }
+    }
+
+    /// Expands or collapses the focused node of the tree.
+    pub fn toggle(&mut self) {
+        if let Some(tree) = self.tree_mut() {
+            tree.toggle();
+        }
+    }
+
+    /// Expands the focused node of the tree.
+    pub fn expand(&mut self) {
+        if let Some(tree) = self.tree_mut() {
+            tree.expand();
+        }
+    }
+
+    /// Collapses the focused node of the tree.
+    pub fn collapse(&mut self) {
+        if let Some(tree) = self.tree_mut() {
+            tree.collapse();
+        }
+    }
+
+    /// Returns the number of lines scrolled by a page.
+    pub fn page(&self) -> u16 {
+        self.height.max(1)
+    }
+
+    /// Renders the message at the index, pretty-printing its payload in the format if
+    /// there is one.
+    pub fn render(
+        &mut self,
+        f: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        (idx, msg): (usize, &MessageRecord),
+        format: Option<Format>,
+    ) {
+        self.height = area.height.saturating_sub(2);
+        if self
+            .text
+            .as_ref()
+            .is_none_or(|(text_idx, _)| *text_idx != idx)
+        {
+            self.text = Some((idx, lines(msg, format)));
+            self.tree = None;
+        }
+        if self.show_tree && self.tree.is_none() {
+            self.tree = Some(Tree::parse(&payload(msg, format).1));
+        }
+
+        let view = self
+            .tree_mut()
+            .map(|tree| (tree.path(), tree.lines(), tree.focus()));
+        let is_tree = view.is_some();
+        let (title, lines) = match view {
+            Some((path, lines, focus)) => {
+                // keep the focused node in view
+                let focus = focus.try_into().unwrap_or(u16::MAX);
+                if focus < self.scroll {
+                    self.scroll = focus;
+                } else if focus >= self.scroll + self.page() {
+                    self.scroll = focus + 1 - self.page();
+                }
+
+                (format!("Tree {path}"), lines)
+            }
+            None => {
+                let title = if self.show_tree {
+                    "Detail (not a tree)"
+                } else {
+                    "Detail"
+                };
+                let lines = self.text.as_ref().map(|(_, lines)| lines.clone());
+                (title.to_string(), lines.unwrap_or_default())
+            }
+        };
+        self.len = lines.len().try_into().unwrap_or(u16::MAX);
+        self.scroll = self.scroll.min(self.len.saturating_sub(1));
+
+        let block = ratatui::widgets::Block::default()
+            .borders(ratatui::widgets::Borders::all())
+            .title(format!("{title} ({}/{})", self.scroll + 1, self.len));
+        let widget = ratatui::widgets::Paragraph::new(lines)
+            .block(block)
+            .scroll((self.scroll, 0));
+        // wrapped lines would throw off keeping the focused node in view
+        let widget = if is_tree {
+            widget
+        } else {
+            widget.wrap(ratatui::widgets::Wrap { trim: false })
+        };
+        f.render_widget(widget, area);
+    }
+
+    /// Returns the tree if it is shown.
+    fn tree_mut(&mut self) -> Option<&mut Tree> {
+        match &mut self.tree {
+            Some(Some(tree)) if self.show_tree => Some(tree),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the title and the payload of a message, pretty-printed in the format if possible.
+fn payload(msg: &MessageRecord, format: Option<Format>) -> (String, String) {
+    let pretty = format.zip(msg.raw.as_ref()).and_then(|(format, raw)| {
+        format
+            .pretty(raw)
+            .map(|pretty| (format!("payload ({format})"), pretty))
+    });
+    match (pretty, &msg.decoded) {
+        (Some(pretty), _) => pretty,
+        (None, Some(decoded)) => ("payload (debug)".to_string(), decoded.clone()),
+        (None, None) => ("text".to_string(), msg.text.clone()),
+    }
+}
+
+/// Lays out the metadata, payload and raw frame of a message.
+fn lines(msg: &MessageRecord, format: Option<Format>) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!("kind: {}", msg.kind.label())),
+        Line::from(format!(
+            "received at: {}",
+            msg.received_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S%.3f %:z")
+        )),
+    ];
+    if let Some(timestamp) = &msg.timestamp {
+        lines.push(Line::from(format!(
+            "timestamp: {}",
+            timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S%.3f %:z")
+        )));
+    }
+    if msg.raw_send {
+        lines.push(Line::from("sent in raw mode, bypassing the action format"));
+    }
+    if let Some(id) = &msg.correlation {
+        lines.push(Line::from(format!("correlation id: {id}")));
+    }
+    if let Some(request) = msg.response_to {
+        lines.push(Line::from(format!("response to: #{}", request + 1)));
+    }
+    if let Some(latency) = msg.latency_ms {
+        lines.push(Line::from(format!("latency: {latency:.1} ms")));
+    }
+    if let Some(raw) = &msg.raw {
+        let (kind, len) = match raw {
+            RawFrame::Text(text) => ("text", text.len()),
+            RawFrame::Binary(bytes) => ("binary", bytes.len()),
+        };
+        lines.push(Line::from(format!("frame: {kind}, {len} bytes")));
+    }
+
+    let (title, payload) = payload(msg, format);
+    lines.push(Line::default());
+    lines.push(Line::from(title).bold());
+    lines.extend(payload.lines().map(|line| Line::from(line.to_string())));
+
+    if let Some(raw) = &msg.raw {
+        lines.push(Line::default());
+        lines.push(Line::from("raw frame").bold());
+        match raw {
+            RawFrame::Text(text) => {
+                lines.extend(text.lines().map(|line| Line::from(line.to_string())))
+            }
+            RawFrame::Binary(bytes) => lines.extend(dump(bytes).map(Line::from)),
+        }
+    }
+
+    lines
+}
+
+/// Formats bytes as a hex dump with offsets and printable characters.
+fn dump(bytes: &[u8]) -> impl Iterator<Item = String> + '_ {
+    bytes.chunks(DUMP_WIDTH).enumerate().map(|(idx, chunk)| {
+        let hex = chunk
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+
+        format!(
+            "{:08x}  {hex:<width$}  {ascii}",
+            idx * DUMP_WIDTH,
+            width = DUMP_WIDTH * 3 - 1
+        )
+    })
+}
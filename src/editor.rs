@@ -0,0 +1,538 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The maximum number of lines the input box grows to before scrolling.
+const MAX_VISIBLE_LINES: usize = 8;
+
+/// A position in the editor, as a line index and a byte offset into that line.
+///
+/// The offset always lies on a grapheme boundary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Pos {
+    row: usize,
+    col: usize,
+}
+
+/// A multi-line text editor for composing requests.
+#[derive(Debug, Clone)]
+pub(crate) struct Editor {
+    /// The lines of text, never empty.
+    lines: Vec<String>,
+    cursor: Pos,
+    /// The other end of the selection, if one is active.
+    anchor: Option<Pos>,
+    /// The display column kept while moving between lines of different lengths.
+    goal: Option<usize>,
+    /// The first visible line and column.
+    scroll: (u16, u16),
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            cursor: Default::default(),
+            anchor: None,
+            goal: None,
+            scroll: (0, 0),
+        }
+    }
+}
+
+impl Editor {
+    /// Returns the whole text.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Returns whether there is no text.
+    pub fn is_empty(&self) -> bool {
+        self.lines.len() == 1 && self.lines[0].is_empty()
+    }
+
+    /// Removes all text.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Replaces the text as given, placing the cursor at its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.clear();
+        self.insert_verbatim(text);
+    }
+
+    /// Returns the height of the input box for the current text, including borders.
+    pub fn height(&self) -> u16 {
+        self.lines.len().clamp(1, MAX_VISIBLE_LINES) as u16 + 2
+    }
+
+    /// Handles a key press, returning whether it was consumed.
+    ///
+    /// Up on the first line and Down on the last line are not consumed.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Left if ctrl || alt => self.move_to(self.word_left(), shift),
+            KeyCode::Left => self.move_to(self.grapheme_left(), shift),
+            KeyCode::Right if ctrl || alt => self.move_to(self.word_right(), shift),
+            KeyCode::Right => self.move_to(self.grapheme_right(), shift),
+            KeyCode::Up => {
+                if self.cursor.row == 0 && !shift {
+                    return false;
+                }
+                self.move_vertical(-1, shift);
+            }
+            KeyCode::Down => {
+                if self.cursor.row + 1 == self.lines.len() && !shift {
+                    return false;
+                }
+                self.move_vertical(1, shift);
+            }
+            KeyCode::Home if ctrl => self.move_to(Pos::default(), shift),
+            KeyCode::Home => self.move_to(
+                Pos {
+                    row: self.cursor.row,
+                    col: 0,
+                },
+                shift,
+            ),
+            KeyCode::End if ctrl => self.move_to(self.end(), shift),
+            KeyCode::End => self.move_to(
+                Pos {
+                    row: self.cursor.row,
+                    col: self.lines[self.cursor.row].len(),
+                },
+                shift,
+            ),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_back(),
+            KeyCode::Backspace => {
+                if !self.delete_selection() {
+                    self.delete_range(self.grapheme_left(), self.cursor);
+                }
+            }
+            KeyCode::Delete => {
+                if !self.delete_selection() {
+                    self.delete_range(self.cursor, self.grapheme_right());
+                }
+            }
+            KeyCode::Enter => self.insert_str("\n"),
+            KeyCode::Char('a') if ctrl => {
+                self.anchor = Some(Pos::default());
+                self.cursor = self.end();
+            }
+            KeyCode::Char('w') if ctrl => self.delete_word_back(),
+            KeyCode::Char('u') if ctrl => {
+                if !self.delete_selection() {
+                    let start = Pos {
+                        row: self.cursor.row,
+                        col: 0,
+                    };
+                    self.delete_range(start, self.cursor);
+                }
+            }
+            KeyCode::Char('k') if ctrl => {
+                if !self.delete_selection() {
+                    let end = Pos {
+                        row: self.cursor.row,
+                        col: self.lines[self.cursor.row].len(),
+                    };
+                    self.delete_range(self.cursor, end);
+                }
+            }
+            KeyCode::Char(ch) if !ctrl && !alt => self.insert_str(ch.encode_utf8(&mut [0; 4])),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Inserts text at the cursor, replacing the selection.
+    ///
+    /// Tabs are expanded to spaces and other control characters dropped.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text
+            .replace("\r\n", "\n")
+            .replace('\t', "    ")
+            .split('\n')
+            .map(strip_control)
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.insert_verbatim(&text);
+    }
+
+    /// Inserts text at the cursor exactly as given, replacing the selection.
+    ///
+    /// Control characters are kept and shown as control pictures, e.g. `␉` for a tab.
+    pub fn insert_verbatim(&mut self, text: &str) {
+        self.delete_selection();
+        self.goal = None;
+
+        let rest = self.lines[self.cursor.row].split_off(self.cursor.col);
+        let mut parts = text.split('\n');
+        if let Some(first) = parts.next() {
+            self.lines[self.cursor.row].push_str(first);
+        }
+        for part in parts {
+            self.cursor.row += 1;
+            self.lines.insert(self.cursor.row, part.to_string());
+        }
+        self.cursor.col = self.lines[self.cursor.row].len();
+        self.lines[self.cursor.row].push_str(&rest);
+    }
+
+    /// Renders the editor, placing the terminal cursor if focused.
+    pub fn render(
+        &mut self,
+        f: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        block: ratatui::widgets::Block,
+        focused: bool,
+    ) {
+        let inner = block.inner(area);
+        let cursor_x = display_width(&self.lines[self.cursor.row][..self.cursor.col]) as u16;
+        let cursor_y = self.cursor.row as u16;
+
+        // keep the cursor in view
+        let (mut scroll_y, mut scroll_x) = self.scroll;
+        if cursor_y < scroll_y {
+            scroll_y = cursor_y;
+        } else if cursor_y >= scroll_y + inner.height.max(1) {
+            scroll_y = cursor_y + 1 - inner.height.max(1);
+        }
+        if cursor_x < scroll_x {
+            scroll_x = cursor_x;
+        } else if cursor_x >= scroll_x + inner.width.max(1) {
+            scroll_x = cursor_x + 1 - inner.width.max(1);
+        }
+        self.scroll = (scroll_y, scroll_x);
+
+        let selection = self.selection();
+        let lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(row, line)| {
+                let Some((start, end)) =
+                    selection.filter(|(start, end)| (start.row..=end.row).contains(&row))
+                else {
+                    return ratatui::text::Line::from(shown(line));
+                };
+
+                let from = if row == start.row { start.col } else { 0 };
+                let to = if row == end.row { end.col } else { line.len() };
+                ratatui::text::Line::from(vec![
+                    ratatui::text::Span::raw(shown(&line[..from])),
+                    ratatui::text::Span::styled(
+                        shown(&line[from..to]),
+                        ratatui::style::Style::default()
+                            .add_modifier(ratatui::style::Modifier::REVERSED),
+                    ),
+                    ratatui::text::Span::raw(shown(&line[to..])),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let widget = ratatui::widgets::Paragraph::new(lines)
+            .block(block)
+            .scroll(self.scroll);
+        f.render_widget(widget, area);
+
+        if focused {
+            f.set_cursor_position((inner.x + cursor_x - scroll_x, inner.y + cursor_y - scroll_y));
+        }
+    }
+
+    /// Moves the cursor, extending the selection if requested.
+    fn move_to(&mut self, pos: Pos, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = pos;
+        self.goal = None;
+    }
+
+    /// Moves the cursor up or down by a line, keeping its display column.
+    fn move_vertical(&mut self, delta: isize, select: bool) {
+        let goal = self
+            .goal
+            .unwrap_or_else(|| display_width(&self.lines[self.cursor.row][..self.cursor.col]));
+        let row = self
+            .cursor
+            .row
+            .saturating_add_signed(delta)
+            .min(self.lines.len() - 1);
+        let col = col_at_width(&self.lines[row], goal);
+
+        self.move_to(Pos { row, col }, select);
+        self.goal = Some(goal);
+    }
+
+    /// Returns the position of the end of the text.
+    fn end(&self) -> Pos {
+        let row = self.lines.len() - 1;
+        Pos {
+            row,
+            col: self.lines[row].len(),
+        }
+    }
+
+    /// Returns the position one grapheme to the left of the cursor.
+    fn grapheme_left(&self) -> Pos {
+        let Pos { row, col } = self.cursor;
+        if col > 0 {
+            let col = self.lines[row][..col]
+                .grapheme_indices(true)
+                .next_back()
+                .map_or(0, |(idx, _)| idx);
+            Pos { row, col }
+        } else if row > 0 {
+            Pos {
+                row: row - 1,
+                col: self.lines[row - 1].len(),
+            }
+        } else {
+            self.cursor
+        }
+    }
+
+    /// Returns the position one grapheme to the right of the cursor.
+    fn grapheme_right(&self) -> Pos {
+        let Pos { row, col } = self.cursor;
+        let line = &self.lines[row];
+        if col < line.len() {
+            let len = line[col..].graphemes(true).next().map_or(0, str::len);
+            Pos {
+                row,
+                col: col + len,
+            }
+        } else if row + 1 < self.lines.len() {
+            Pos {
+                row: row + 1,
+                col: 0,
+            }
+        } else {
+            self.cursor
+        }
+    }
+
+    /// Returns the position of the start of the word left of the cursor.
+    fn word_left(&self) -> Pos {
+        let Pos { row, col } = self.cursor;
+        if col == 0 {
+            return self.grapheme_left();
+        }
+
+        let mut graphemes = self.lines[row][..col]
+            .grapheme_indices(true)
+            .rev()
+            .peekable();
+        while graphemes.next_if(|(_, g)| is_space(g)).is_some() {}
+        let mut col = graphemes.peek().map_or(0, |(idx, _)| *idx);
+        while let Some((idx, _)) = graphemes.next_if(|(_, g)| !is_space(g)) {
+            col = idx;
+        }
+
+        Pos { row, col }
+    }
+
+    /// Returns the position of the end of the word right of the cursor.
+    fn word_right(&self) -> Pos {
+        let Pos { row, col } = self.cursor;
+        let line = &self.lines[row];
+        if col == line.len() {
+            return self.grapheme_right();
+        }
+
+        let mut graphemes = line[col..].grapheme_indices(true).peekable();
+        while graphemes.next_if(|(_, g)| is_space(g)).is_some() {}
+        while graphemes.next_if(|(_, g)| !is_space(g)).is_some() {}
+        let offset = graphemes.peek().map_or(line.len() - col, |(idx, _)| *idx);
+
+        Pos {
+            row,
+            col: col + offset,
+        }
+    }
+
+    /// Returns the ordered bounds of the selection, if it is not empty.
+    fn selection(&self) -> Option<(Pos, Pos)> {
+        let anchor = self.anchor.filter(|anchor| *anchor != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Deletes the selected text, returning whether there was any.
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+
+        self.delete_range(start, end);
+        true
+    }
+
+    /// Deletes the word left of the cursor, or the selection.
+    fn delete_word_back(&mut self) {
+        if !self.delete_selection() {
+            self.delete_range(self.word_left(), self.cursor);
+        }
+    }
+
+    /// Deletes the text between two ordered positions and moves the cursor to the start.
+    fn delete_range(&mut self, start: Pos, end: Pos) {
+        if start.row == end.row {
+            self.lines[start.row].replace_range(start.col..end.col, "");
+        } else {
+            let tail = self.lines[end.row][end.col..].to_string();
+            self.lines[start.row].truncate(start.col);
+            self.lines[start.row].push_str(&tail);
+            self.lines.drain(start.row + 1..=end.row);
+        }
+
+        self.cursor = start;
+        self.anchor = None;
+        self.goal = None;
+    }
+}
+
+/// Returns the byte offset of the grapheme at the given display column.
+fn col_at_width(line: &str, width: usize) -> usize {
+    let mut acc = 0;
+    for (idx, grapheme) in line.grapheme_indices(true) {
+        let grapheme_width = display_width(grapheme);
+        if acc + grapheme_width > width {
+            return idx;
+        }
+        acc += grapheme_width;
+    }
+
+    line.len()
+}
+
+/// Returns whether a grapheme is whitespace.
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Removes control characters that would corrupt the rendered text.
+fn strip_control(text: &str) -> String {
+    text.chars().filter(|ch| !ch.is_control()).collect()
+}
+
+/// Replaces control characters with their control pictures for rendering.
+fn shown(text: &str) -> std::borrow::Cow<'_, str> {
+    if !text.contains(char::is_control) {
+        return text.into();
+    }
+
+    text.chars()
+        .map(|ch| match ch {
+            '\0'..='\x1f' => char::from_u32(0x2400 + ch as u32).unwrap_or(ch),
+            '\x7f' => '␡',
+            ch if ch.is_control() => '�',
+            ch => ch,
+        })
+        .collect::<String>()
+        .into()
+}
+
+/// Returns the display width of text as rendered by [`shown`].
+fn display_width(text: &str) -> usize {
+    shown(text).width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut Editor, code: KeyCode, modifiers: KeyModifiers) {
+        editor.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn moves_and_deletes_by_grapheme() {
+        let mut editor = Editor::default();
+        // a combining accent and an emoji with a skin tone modifier
+        editor.set_text("e\u{301}👍🏽x");
+        press(&mut editor, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 11 });
+        press(&mut editor, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 3 });
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "👍🏽x");
+
+        press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "x");
+        press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 1 });
+    }
+
+    #[test]
+    fn moves_by_word_across_lines() {
+        let mut editor = Editor::default();
+        editor.set_text("foo  bär-baz\n  qux");
+        press(&mut editor, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 1, col: 2 });
+        press(&mut editor, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 1, col: 0 });
+        press(&mut editor, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 13 });
+        press(&mut editor, KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 5 });
+
+        press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 3 });
+        press(&mut editor, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 13 });
+        press(&mut editor, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(editor.cursor, Pos { row: 1, col: 0 });
+
+        press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(editor.text(), "foo  bär-baz\n  ");
+    }
+
+    #[test]
+    fn deletes_ranges_spanning_lines() {
+        let mut editor = Editor::default();
+        editor.set_text("one\ntwo\nthree");
+        editor.delete_range(Pos { row: 0, col: 1 }, Pos { row: 2, col: 2 });
+        assert_eq!(editor.text(), "oree");
+        assert_eq!(editor.cursor, Pos { row: 0, col: 1 });
+
+        // a selection made with Shift deletes the same way
+        editor.set_text("one\ntwo\nthree");
+        press(&mut editor, KeyCode::Up, KeyModifiers::SHIFT);
+        press(&mut editor, KeyCode::Home, KeyModifiers::SHIFT);
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "one\n");
+        assert_eq!(editor.cursor, Pos { row: 1, col: 0 });
+
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "one");
+        assert_eq!(editor.lines.len(), 1);
+    }
+
+    #[test]
+    fn pastes_verbatim_or_cleaned() {
+        let mut editor = Editor::default();
+        editor.insert_str("a\tb\x07\r\nc");
+        assert_eq!(editor.text(), "a    b\nc");
+
+        editor.set_text("a\tb\r\nc");
+        assert_eq!(editor.text(), "a\tb\r\nc");
+        assert_eq!(shown(&editor.lines[0]), "a␉b␍");
+        press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, Pos { row: 0, col: 1 });
+        press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(display_width(&editor.lines[0][..editor.cursor.col]), 2);
+    }
+}
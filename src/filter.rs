@@ -0,0 +1,227 @@
+use crate::record::{MessageKind, MessageRecord};
+
+/// A filter expression hiding messages from the Events list.
+///
+/// The expression is made of terms separated by whitespace, all of which have to match:
+/// - `kind:sent,error` matches messages of any of the kinds,
+/// - `variant:Foo` matches actions, events and errors of the enum variant,
+/// - `/pattern/` matches the text of the message against a regex, which may contain
+///   whitespace as long as the closing `/` ends the term,
+/// - `!term` matches if the term does not,
+/// - anything else matches the text as a case-insensitive substring.
+///
+/// The text is the one shown after the time and kind in the Events list.
+#[derive(Debug, Clone)]
+pub(crate) struct Filter {
+    expr: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Kind(Vec<MessageKind>),
+    Variant(String),
+    Regex(regex::Regex),
+    Substring(String),
+    Not(Box<Term>),
+}
+
+impl Filter {
+    /// Returns whether the message passes the filter.
+    pub fn matches(&self, msg: &MessageRecord) -> bool {
+        self.terms.iter().all(|term| term.matches(msg))
+    }
+}
+
+impl Term {
+    fn parse(term: &str) -> color_eyre::Result<Self> {
+        if let Some(term) = term.strip_prefix('!') {
+            return Ok(Term::Not(Box::new(Term::parse(term)?)));
+        }
+        if let Some(kinds) = term.strip_prefix("kind:") {
+            let kinds = kinds
+                .split(',')
+                .map(str::parse)
+                .collect::<color_eyre::Result<_>>()?;
+            return Ok(Term::Kind(kinds));
+        }
+        if let Some(variant) = term.strip_prefix("variant:") {
+            return Ok(Term::Variant(variant.to_string()));
+        }
+        if let Some(pattern) = term
+            .strip_prefix('/')
+            .and_then(|term| term.strip_suffix('/'))
+            .filter(|pattern| !pattern.is_empty())
+        {
+            return Ok(Term::Regex(regex::Regex::new(pattern)?));
+        }
+
+        Ok(Term::Substring(term.to_ascii_lowercase()))
+    }
+
+    fn matches(&self, msg: &MessageRecord) -> bool {
+        match self {
+            Term::Kind(kinds) => kinds.contains(&msg.kind),
+            Term::Variant(variant) => msg.variant.as_ref() == Some(variant),
+            Term::Regex(regex) => regex.is_match(&msg.text),
+            Term::Substring(substring) => msg.text.to_ascii_lowercase().contains(substring),
+            Term::Not(term) => !term.matches(msg),
+        }
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let terms = split_terms(s)
+            .into_iter()
+            .map(Term::parse)
+            .collect::<color_eyre::Result<_>>()?;
+
+        Ok(Self {
+            expr: s.trim().to_string(),
+            terms,
+        })
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+/// Splits an expression on whitespace, except inside `/regex/` terms.
+fn split_terms(s: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let negated = rest.trim_start_matches('!');
+        let regex_end = negated
+            .strip_prefix('/')
+            .and_then(closing_slash)
+            .map(|end| rest.len() - negated.len() + 1 + end + 1);
+        let end = regex_end
+            .or_else(|| rest.find(char::is_whitespace))
+            .unwrap_or(rest.len());
+
+        terms.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+
+    terms
+}
+
+/// Returns the offset of the unescaped `/` closing a regex and its term.
+fn closing_slash(regex: &str) -> Option<usize> {
+    let mut escaped = false;
+    regex.char_indices().find_map(|(idx, ch)| {
+        let closes = ch == '/'
+            && !escaped
+            && regex[idx + 1..]
+                .chars()
+                .next()
+                .is_none_or(char::is_whitespace);
+        escaped = ch == '\\' && !escaped;
+        closes.then_some(idx)
+    })
+}
+
+/// Finds the case-insensitive occurrences of the query in the line, as byte ranges.
+pub(crate) fn find(line: &str, query: &str) -> Vec<std::ops::Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // ASCII lowercasing keeps the byte offsets intact
+    let line = line.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    line.match_indices(&query)
+        .map(|(start, _)| start..start + query.len())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(kind: MessageKind, variant: Option<&str>) -> MessageRecord {
+        let mut msg = MessageRecord::new(kind, "");
+        msg.variant = variant.map(str::to_string);
+        msg
+    }
+
+    #[test]
+    fn terms_all_have_to_match() {
+        let filter: Filter = "  kind:sent,received  variant:Login  ".parse().unwrap();
+        assert_eq!(filter.to_string(), "kind:sent,received  variant:Login");
+        assert!(filter.matches(&msg(MessageKind::Sent, Some("Login"))));
+        assert!(filter.matches(&msg(MessageKind::Received, Some("Login"))));
+        assert!(!filter.matches(&msg(MessageKind::Error, Some("Login"))));
+        assert!(!filter.matches(&msg(MessageKind::Sent, Some("Logout"))));
+        assert!(!filter.matches(&msg(MessageKind::Sent, None)));
+
+        let filter: Filter = "".parse().unwrap();
+        assert!(filter.matches(&msg(MessageKind::System, None)));
+    }
+
+    #[test]
+    fn text_terms() {
+        let text = |text: &str| MessageRecord::system(text);
+        let filter: Filter = "BOB !/^Logout/".parse().unwrap();
+        assert!(filter.matches(&text("Login bob")));
+        assert!(!filter.matches(&text("Logout bob")));
+        assert!(!filter.matches(&text("Login alice")));
+
+        // anchored to the text, not the time and kind in front of it
+        let filter: Filter = "/^connected to/".parse().unwrap();
+        let connected = text("connected to ws://host");
+        assert!(connected.to_string().contains(": connected to"));
+        assert!(filter.matches(&connected));
+
+        // an empty regex is a substring
+        let filter: Filter = "//".parse().unwrap();
+        assert!(filter.matches(&text("ws://host")));
+        assert!(!filter.matches(&text("host")));
+
+        let filter: Filter = "!kind:system".parse().unwrap();
+        assert!(!filter.matches(&text("")));
+    }
+
+    #[test]
+    fn regex_terms_may_contain_whitespace() {
+        assert_eq!(
+            split_terms(r"kind:sent !/^Login \/ bob$/ /a/b x/"),
+            ["kind:sent", r"!/^Login \/ bob$/", "/a/b x/"]
+        );
+        // a slash not ending a term does not close the regex
+        assert_eq!(split_terms("/api/v1 bob"), ["/api/v1", "bob"]);
+
+        let filter: Filter = "/^Login bob$/".parse().unwrap();
+        assert!(filter.matches(&MessageRecord::system("Login bob")));
+        assert!(!filter.matches(&MessageRecord::system("Login bobby")));
+    }
+
+    #[test]
+    fn invalid_terms_are_errors() {
+        assert!("kind:sent,bogus".parse::<Filter>().is_err());
+        assert!("/(/".parse::<Filter>().is_err());
+        assert!("!/[/".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn find_returns_byte_ranges() {
+        assert!(find("abc", "").is_empty());
+        assert_eq!(find("Abc aBC", "abc"), [0..3, 4..7]);
+
+        // multi-byte characters before and inside a match
+        let line = "日本語 naïve Naïve";
+        let ranges = find(line, "NAïVE");
+        assert_eq!(ranges, [10..16, 17..23]);
+        assert_eq!(&line[ranges[1].clone()], "Naïve");
+
+        // only ASCII is case-insensitive
+        assert_eq!(find("GRÜN grün", "grün"), vec![6..11]);
+    }
+}
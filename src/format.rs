@@ -0,0 +1,355 @@
+use crate::record::RawFrame;
+
+/// A serialization format, selected at runtime among the compiled-in ones.
+///
+/// The `in-*` features control which formats are available for the requests typed into
+/// the input box, while the `out-*` features control the formats spoken on the wire.
+///
+/// Binary formats are sent as binary frames and are written as hex when represented as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    #[cfg(any(feature = "in-json", feature = "out-json"))]
+    Json,
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    Ron,
+    #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+    MsgPack,
+    #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+    Cbor,
+}
+
+impl Format {
+    /// Returns the default format for the input box.
+    pub fn default_in() -> Self {
+        #[cfg(feature = "in-json")]
+        return Format::Json;
+        #[cfg(all(not(feature = "in-json"), feature = "in-ron"))]
+        return Format::Ron;
+        #[cfg(all(
+            not(any(feature = "in-json", feature = "in-ron")),
+            feature = "in-msgpack"
+        ))]
+        return Format::MsgPack;
+        #[cfg(all(
+            not(any(feature = "in-json", feature = "in-ron", feature = "in-msgpack")),
+            feature = "in-cbor"
+        ))]
+        return Format::Cbor;
+        #[cfg(not(any(
+            feature = "in-json",
+            feature = "in-ron",
+            feature = "in-msgpack",
+            feature = "in-cbor"
+        )))]
+        unreachable!()
+    }
+
+    /// Returns the default format for frames on the wire.
+    pub fn default_out() -> Self {
+        #[cfg(feature = "out-json")]
+        return Format::Json;
+        #[cfg(all(not(feature = "out-json"), feature = "out-ron"))]
+        return Format::Ron;
+        #[cfg(all(
+            not(any(feature = "out-json", feature = "out-ron")),
+            feature = "out-msgpack"
+        ))]
+        return Format::MsgPack;
+        #[cfg(all(
+            not(any(feature = "out-json", feature = "out-ron", feature = "out-msgpack")),
+            feature = "out-cbor"
+        ))]
+        return Format::Cbor;
+        #[cfg(not(any(
+            feature = "out-json",
+            feature = "out-ron",
+            feature = "out-msgpack",
+            feature = "out-cbor"
+        )))]
+        unreachable!()
+    }
+
+    /// Returns whether the format may be used for the input box, i.e. its `in-*` feature
+    /// is enabled.
+    pub fn is_in(self) -> bool {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => cfg!(feature = "in-json"),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => cfg!(feature = "in-ron"),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => cfg!(feature = "in-msgpack"),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => cfg!(feature = "in-cbor"),
+        }
+    }
+
+    /// Returns whether the format may be used on the wire, i.e. its `out-*` feature is
+    /// enabled.
+    pub fn is_out(self) -> bool {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => cfg!(feature = "out-json"),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => cfg!(feature = "out-ron"),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => cfg!(feature = "out-msgpack"),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => cfg!(feature = "out-cbor"),
+        }
+    }
+
+    /// Returns whether the format is sent as binary frames.
+    pub fn is_binary(self) -> bool {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => false,
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => false,
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => true,
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => true,
+        }
+    }
+
+    /// Serializes the value into bytes.
+    pub fn to_bytes<T>(self, value: &T) -> color_eyre::Result<Vec<u8>>
+    where
+        T: serde::Serialize,
+    {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Ok(ron::to_string(value)?.into_bytes()),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => Ok(rmp_serde::to_vec_named(value)?),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Deserializes a value from bytes.
+    pub fn from_bytes<T>(self, bytes: &[u8]) -> color_eyre::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Ok(ron::de::from_bytes(bytes)?),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => Ok(rmp_serde::from_slice(bytes)?),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => Ok(ciborium::from_reader(bytes)?),
+        }
+    }
+
+    /// Serializes the value into a string.
+    pub fn serialize<T>(self, value: &T) -> color_eyre::Result<String>
+    where
+        T: serde::Serialize,
+    {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Ok(serde_json::to_string(value)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Ok(ron::to_string(value)?),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => Ok(encode_hex(&self.to_bytes(value)?)),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => Ok(encode_hex(&self.to_bytes(value)?)),
+        }
+    }
+
+    /// Deserializes a value from a string.
+    pub fn deserialize<T>(self, text: &str) -> color_eyre::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => Ok(serde_json::from_str(text)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => Ok(ron::from_str(text)?),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => self.from_bytes(&decode_hex(text)?),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => self.from_bytes(&decode_hex(text)?),
+        }
+    }
+
+    /// Pretty-prints a frame payload of this format without knowing its type.
+    ///
+    /// Returns `None` if the payload cannot be parsed as this format.
+    pub(crate) fn pretty(self, raw: &RawFrame) -> Option<String> {
+        match (self, raw) {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            (Format::Json, RawFrame::Text(text)) => {
+                let value = serde_json::from_str::<serde_json::Value>(text).ok()?;
+                serde_json::to_string_pretty(&value).ok()
+            }
+            // RON values drop struct and variant names, so reindent the text instead
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            (Format::Ron, RawFrame::Text(text)) => {
+                ron::from_str::<ron::Value>(text).ok()?;
+                Some(indent(text))
+            }
+            #[cfg(any(
+                feature = "in-msgpack",
+                feature = "out-msgpack",
+                feature = "in-cbor",
+                feature = "out-cbor"
+            ))]
+            (format, RawFrame::Binary(bytes)) if format.is_binary() => {
+                let value = format.from_bytes::<serde_json::Value>(bytes).ok()?;
+                serde_json::to_string_pretty(&value).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Indents bracketed text such as RON, putting every element on its own line.
+#[cfg(any(feature = "in-ron", feature = "out-ron"))]
+fn indent(text: &str) -> String {
+    fn newline(out: &mut String, depth: usize) {
+        out.push('\n');
+        out.push_str(&"    ".repeat(depth));
+    }
+
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if let Some(delim) = quote {
+            out.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == delim {
+                quote = None;
+            }
+            continue;
+        }
+
+        if matches!(ch, '"' | '\'') {
+            quote = Some(ch);
+            out.push(ch);
+            continue;
+        }
+
+        match ch {
+            '(' | '[' | '{' => {
+                while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+                out.push(ch);
+                // keep empty groups on one line
+                if let Some(close) = chars.next_if(|ch| matches!(ch, ')' | ']' | '}')) {
+                    out.push(close);
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(ch);
+            }
+            ',' => {
+                while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+                // drop trailing commas, the closing bracket goes on its own line anyway
+                if !matches!(chars.peek(), Some(')' | ']' | '}')) {
+                    out.push(ch);
+                    newline(&mut out, depth);
+                }
+            }
+            ':' => out.push_str(": "),
+            ch if ch.is_whitespace() => {}
+            ch => out.push(ch),
+        }
+    }
+
+    out
+}
+
+/// Encodes bytes as lowercase hex.
+#[cfg(any(
+    feature = "in-msgpack",
+    feature = "out-msgpack",
+    feature = "in-cbor",
+    feature = "out-cbor"
+))]
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes hex into bytes, ignoring any whitespace in between.
+pub(crate) fn decode_hex(text: &str) -> color_eyre::Result<Vec<u8>> {
+    let digits = text
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        return Err(color_eyre::eyre::eyre!(
+            "hex input has an odd number of digits"
+        ));
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = pair.iter().collect::<String>();
+            // `from_str_radix` takes a sign too
+            u8::from_str_radix(&pair, 16)
+                .ok()
+                .filter(|_| pair.chars().all(|ch| ch.is_ascii_hexdigit()))
+                .ok_or_else(|| color_eyre::eyre::eyre!("invalid hex byte: {pair}"))
+        })
+        .collect()
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            Format::Json => "json",
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Format::Ron => "ron",
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            Format::MsgPack => "msgpack",
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            Format::Cbor => "cbor",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            #[cfg(any(feature = "in-json", feature = "out-json"))]
+            "json" => Ok(Format::Json),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            "ron" => Ok(Format::Ron),
+            #[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+            "msgpack" => Ok(Format::MsgPack),
+            #[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+            "cbor" => Ok(Format::Cbor),
+            _ => Err(color_eyre::eyre::eyre!(
+                "unknown or not compiled-in format: {s}"
+            )),
+        }
+    }
+}
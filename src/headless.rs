@@ -0,0 +1,300 @@
+use crate::record::{MessageKind, MessageRecord};
+use crate::recording::Notation;
+use crate::script::{Pattern, Script, Step};
+use crate::Client;
+use std::io::Write;
+use tokio::io::AsyncBufReadExt;
+use tokio::time::Instant;
+
+/// The most events kept for the next `expect` step while none is waiting.
+const MAX_PENDING: usize = 1000;
+
+/// An `expect` step waiting for its event.
+struct Expectation {
+    /// The line of the step in the script.
+    line: usize,
+    pattern: Pattern,
+    started: Instant,
+}
+
+/// The outcome of an `expect` step.
+struct Outcome {
+    line: usize,
+    pattern: String,
+    /// How long the match took, or why there was none.
+    result: Result<std::time::Duration, String>,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.result {
+            Ok(took) => write!(
+                f,
+                "PASS line {}: expect {} ({} ms)",
+                self.line,
+                self.pattern,
+                took.as_millis()
+            ),
+            Err(reason) => write!(
+                f,
+                "FAIL line {}: expect {}: {reason}",
+                self.line, self.pattern
+            ),
+        }
+    }
+}
+
+impl<Action, Event, Err> Client<Action, Event, Err>
+where
+    Action: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Event: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+    Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    /// Starts the client without a terminal UI.
+    ///
+    /// Runs the script, reading it from stdin if no file is given. Every line is an action
+    /// in [`crate::ClientCfg::in_format`] or a step as described in `SCRIPTS.md`. Received
+    /// events and errors are printed to stdout as JSON lines in the format described in
+    /// `RECORDING.md`, everything else to stderr, ending with a pass/fail report if the
+    /// script has `expect` steps.
+    ///
+    /// Returns whether the script ran through without any unexpected errors or failed
+    /// expectations.
+    pub async fn start_headless(
+        mut self,
+        script: Option<&std::path::Path>,
+    ) -> color_eyre::Result<bool> {
+        self.prepare()?;
+        let mut lines = read_lines(script)?;
+        let mut link = self.link();
+
+        let mut script = Script::new(self.cfg.in_format).verbatim(self.cfg.verbatim_actions);
+        let mut line_no = 0;
+        let mut expecting: Option<Expectation> = None;
+        // events received before the next `expect` step was read
+        let mut pending = std::collections::VecDeque::new();
+        let mut outcomes = Vec::new();
+        let mut msg_no = 0;
+
+        let mut ok = true;
+        let mut connected = false;
+        let mut script_done = false;
+        // a deadline too far to represent is no deadline at all
+        let mut deadline = Instant::now().checked_add(self.cfg.connect_timeout);
+        loop {
+            let timeout = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                msg = link.msg_rx.recv() => {
+                    let Some(mut msg) = msg else {
+                        break;
+                    };
+                    self.tracker.track(&mut msg, msg_no);
+                    msg_no += 1;
+                    self.output(&msg)?;
+                    if let Some(expectation) =
+                        expecting.take_if(|expectation| expectation.pattern.matches(&msg))
+                    {
+                        outcomes.push(Outcome {
+                            line: expectation.line,
+                            pattern: expectation.pattern.to_string(),
+                            result: Ok(expectation.started.elapsed()),
+                        });
+                        deadline = None;
+                    } else if expecting.is_none()
+                        && !script_done
+                        && matches!(msg.kind, MessageKind::Received | MessageKind::Error)
+                    {
+                        if pending.len() == MAX_PENDING {
+                            ok &= pending.pop_front().is_none_or(is_not_error);
+                        }
+                        pending.push_back(msg);
+                    } else {
+                        ok &= is_not_error(msg);
+                    }
+                }
+                changed = link.info_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let now_connected = link.info_rx.borrow_and_update().is_some();
+                    if connected && !now_connected {
+                        // the server may close the connection once it answered everything
+                        ok &= script_done;
+                        break;
+                    }
+                    if now_connected && !connected && !script_done {
+                        deadline = None;
+                    }
+                    connected = now_connected;
+                }
+                line = lines.recv(), if connected && !script_done && expecting.is_none() => match line {
+                    Some(line) => {
+                        line_no += 1;
+                        match script.parse_line::<Action>(&line) {
+                            Ok(None) => {}
+                            Ok(Some(Step::Send(req))) => {
+                                if let Err(err) = link.req_tx.send(req).await {
+                                    ok = false;
+                                    self.output(&MessageRecord::new(
+                                        MessageKind::Error,
+                                        format!(
+                                            "the connection task stopped at line {line_no}, dropped request: {}",
+                                            err.0
+                                        ),
+                                    ))?;
+                                    break;
+                                }
+                            }
+                            Ok(Some(Step::Expect { pattern, timeout })) => {
+                                // the event may have arrived before the step was read
+                                let found = pending.iter().position(|msg| pattern.matches(msg));
+                                let skipped = found.unwrap_or(pending.len());
+                                ok &= pending.drain(..skipped).all(is_not_error);
+                                if found.is_some() {
+                                    pending.pop_front();
+                                    outcomes.push(Outcome {
+                                        line: line_no,
+                                        pattern: pattern.to_string(),
+                                        result: Ok(std::time::Duration::ZERO),
+                                    });
+                                } else {
+                                    let started = Instant::now();
+                                    deadline = started.checked_add(timeout);
+                                    expecting = Some(Expectation {
+                                        line: line_no,
+                                        pattern,
+                                        started,
+                                    });
+                                }
+                            }
+                            Err(err) => {
+                                ok = false;
+                                self.output(&MessageRecord::new(
+                                    MessageKind::Error,
+                                    format!("invalid script line {line_no}: {}: {err}", line.trim()),
+                                ))?;
+                            }
+                        }
+                    }
+                    None => {
+                        script_done = true;
+                        ok &= pending.drain(..).all(is_not_error);
+                        deadline = Instant::now().checked_add(self.cfg.headless_wait);
+                    }
+                },
+                _ = timeout => {
+                    if let Some(expectation) = expecting.take() {
+                        outcomes.push(Outcome {
+                            line: expectation.line,
+                            pattern: expectation.pattern.to_string(),
+                            result: Err(format!(
+                                "no match within {:.1}s",
+                                expectation.started.elapsed().as_secs_f64()
+                            )),
+                        });
+                        deadline = None;
+                        continue;
+                    }
+                    if !connected {
+                        ok = false;
+                        self.output(&MessageRecord::system(format!(
+                            "failed to connect within {:.1}s",
+                            self.cfg.connect_timeout.as_secs_f64()
+                        )))?;
+                    }
+                    break;
+                }
+            }
+        }
+
+        link.shut_down().await;
+
+        ok &= pending.into_iter().all(is_not_error);
+        if let Some(expectation) = expecting {
+            outcomes.push(Outcome {
+                line: expectation.line,
+                pattern: expectation.pattern.to_string(),
+                result: Err("the connection closed first".to_string()),
+            });
+        }
+        if !outcomes.is_empty() {
+            let failed = outcomes
+                .iter()
+                .filter(|outcome| outcome.result.is_err())
+                .count();
+            let mut stderr = std::io::stderr();
+            for outcome in &outcomes {
+                writeln!(stderr, "{outcome}")?;
+            }
+            writeln!(
+                stderr,
+                "{} passed, {failed} failed",
+                outcomes.len() - failed
+            )?;
+            ok &= failed == 0;
+        }
+        for line in self.tracker.summary() {
+            writeln!(std::io::stderr(), "latency {line}")?;
+        }
+
+        Ok(ok)
+    }
+
+    /// Prints a message and writes it to the log file and the recording.
+    fn output(&mut self, msg: &MessageRecord) -> std::io::Result<()> {
+        match msg.kind {
+            MessageKind::Received | MessageKind::Error => {
+                let line = Notation::Json.format_line(msg)?;
+                writeln!(std::io::stdout(), "{line}")?;
+            }
+            MessageKind::Sent | MessageKind::System => writeln!(std::io::stderr(), "{msg}")?,
+        }
+
+        match self.write_msg(msg) {
+            Some(notice) => writeln!(std::io::stderr(), "{notice}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns whether an event no `expect` step matched leaves the run passing.
+fn is_not_error(msg: MessageRecord) -> bool {
+    msg.kind != MessageKind::Error
+}
+
+/// Streams the lines of the script, or of stdin if no file is given.
+fn read_lines(
+    script: Option<&std::path::Path>,
+) -> color_eyre::Result<tokio::sync::mpsc::Receiver<String>> {
+    let (line_tx, line_rx) = tokio::sync::mpsc::channel(100);
+    match script {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)?;
+            tokio::spawn(async move {
+                for line in text.lines() {
+                    if line_tx.send(line.to_string()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        None => {
+            tokio::spawn(async move {
+                let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if line_tx.send(line).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    Ok(line_rx)
+}
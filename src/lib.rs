@@ -1,32 +1,230 @@
+mod codec;
+mod conn;
+mod correlate;
+mod detail;
+mod editor;
+mod filter;
+mod format;
+mod headless;
+mod history;
+mod raw;
+mod record;
+mod recording;
+mod replay;
+mod script;
+mod store;
+#[cfg(feature = "tls")]
+mod tls;
+mod tree;
 mod tui;
 
-use futures::{SinkExt, StreamExt};
-use std::collections::VecDeque;
-use tokio::sync::mpsc::{Receiver, Sender};
+#[cfg(any(feature = "in-cbor", feature = "out-cbor"))]
+pub use codec::CborCodec;
+pub use codec::Codec;
+#[cfg(any(feature = "in-json", feature = "out-json"))]
+pub use codec::JsonCodec;
+#[cfg(any(feature = "in-msgpack", feature = "out-msgpack"))]
+pub use codec::MsgPackCodec;
+#[cfg(any(feature = "in-ron", feature = "out-ron"))]
+pub use codec::RonCodec;
+pub use correlate::Correlate;
+pub use format::Format;
+pub use history::default_dir as default_history_dir;
+pub use replay::ReplayPace;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
 pub use tui::Tui;
 
 #[cfg(not(all(
-    any(feature = "out-json", feature = "out-ron"),
-    any(feature = "in-json", feature = "in-ron")
+    any(
+        feature = "out-json",
+        feature = "out-ron",
+        feature = "out-msgpack",
+        feature = "out-cbor"
+    ),
+    any(
+        feature = "in-json",
+        feature = "in-ron",
+        feature = "in-msgpack",
+        feature = "in-cbor"
+    )
 )))]
 compile_error!("need at least one input and one output feature-flag enabled");
 
 /// The type for incoming messages.
 pub type Res<Event, Err> = std::result::Result<wire::TimestampedEvent<Event>, Err>;
 
-/// The maximum number of messages in the message history buffer.
-const MAX_MESSAGES: usize = 100;
-
 /// The state of the app.
 enum State {
     InputSelected,
     MsgListSelected,
 }
 
-/// Configures the client externally.
+/// A line of text being typed in VIEW mode.
+enum Prompt {
+    /// A search through the Events list, applied while typing.
+    Find(String),
+    /// A filter expression, applied once confirmed.
+    Filter(String),
+}
+
+/// A failure that keeps the client from talking to the server until it reconnects.
 #[derive(Debug, Clone, PartialEq, Eq)]
+enum ClientError {
+    /// The connection task panicked, with the panic message.
+    ConnectionPanicked(String),
+    /// The connection task stopped for another reason.
+    ConnectionStopped,
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::ConnectionPanicked(msg) => {
+                write!(f, "the connection task panicked: {msg}")
+            }
+            ClientError::ConnectionStopped => f.write_str("the connection task stopped"),
+        }
+    }
+}
+
+/// Installs the color_eyre hooks, restoring the terminal before a panic is reported.
+fn install_hooks() -> color_eyre::Result<()> {
+    let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+    std::panic::set_hook(Box::new(move |info| {
+        // a failed restore must not keep the report from printing
+        let _ = tui::restore();
+        eprintln!("{}", panic_hook.panic_report(info));
+    }));
+
+    Ok(())
+}
+
+/// Configures the client externally.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClientCfg {
     pub url: String,
+    /// Extra headers sent with the WebSocket handshake request.
+    pub headers: tokio_tungstenite::tungstenite::http::HeaderMap,
+    /// The subprotocols offered to the server, in order of preference.
+    pub subprotocols: Vec<String>,
+    /// The format of the requests typed into the input box.
+    pub in_format: Format,
+    /// The format of the frames sent to and received from the server.
+    pub out_format: Format,
+    /// Sends typed requests as text frames exactly as written, after checking they parse
+    /// as an action in [`ClientCfg::in_format`].
+    ///
+    /// Meant for untyped actions like `ron::Value`, which drop struct and variant names
+    /// when encoded again. Needs the same text format for the input box and the wire.
+    pub verbatim_actions: bool,
+    /// The maximum number of messages in the message history, unbounded if unset.
+    pub max_messages: Option<usize>,
+    /// The number of messages kept in memory.
+    ///
+    /// Older messages are moved to a temporary file and paged back in when scrolling up.
+    pub memory_messages: usize,
+    /// The number of ticks per second.
+    pub tick_rate: f64,
+    /// The number of frames rendered per second.
+    pub frame_rate: f64,
+    /// A file every line of the message history is appended to.
+    pub log_file: Option<std::path::PathBuf>,
+    /// A file the session is recorded to, see `RECORDING.md` for the format.
+    pub record_file: Option<std::path::PathBuf>,
+    /// A recording whose actions are sent again once connected.
+    pub replay_file: Option<std::path::PathBuf>,
+    /// How the actions of [`ClientCfg::replay_file`] are paced.
+    pub replay_pace: ReplayPace,
+    /// How long [`Client::start_headless`] waits for the first connection.
+    pub connect_timeout: Duration,
+    /// How long [`Client::start_headless`] keeps printing events after the script ended.
+    pub headless_wait: Duration,
+    /// The directory sent requests are saved in, one file per url.
+    ///
+    /// The request history is not persisted if unset, which is the default. See
+    /// [`default_history_dir`] for the directory the command line uses.
+    pub history_dir: Option<std::path::PathBuf>,
+    /// A PEM bundle of extra CA certificates to trust.
+    #[cfg(feature = "tls")]
+    pub tls_ca_file: Option<std::path::PathBuf>,
+    /// A PEM client certificate chain presented for mutual TLS.
+    #[cfg(feature = "tls")]
+    pub tls_client_cert: Option<std::path::PathBuf>,
+    /// The PEM private key of [`ClientCfg::tls_client_cert`].
+    #[cfg(feature = "tls")]
+    pub tls_client_key: Option<std::path::PathBuf>,
+    /// Skips verifying the server certificate.
+    ///
+    /// Only meant for testing against local servers with self-signed certificates. Cannot
+    /// be combined with [`ClientCfg::tls_ca_file`].
+    #[cfg(feature = "tls")]
+    pub tls_insecure: bool,
+    /// The delay before the first reconnect attempt.
+    pub reconnect_delay: Duration,
+    /// The upper bound for the delay between reconnect attempts.
+    pub reconnect_max_delay: Duration,
+    /// The factor the delay is multiplied by after every failed attempt.
+    pub reconnect_factor: u32,
+    /// The upper bound for the random delay added to every attempt.
+    pub reconnect_jitter: Duration,
+    /// How often a ping is sent to measure the round-trip time, never if unset.
+    pub ping_interval: Option<Duration>,
+    /// How long the connection may stay without any frame before it counts as dead and is
+    /// reconnected, forever if unset.
+    ///
+    /// Only applies while [`ClientCfg::ping_interval`] is set, as the pongs are what keeps
+    /// a quiet connection from looking dead.
+    pub idle_timeout: Option<Duration>,
+    /// The code of the close frame sent on exit.
+    ///
+    /// Codes that may not be sent, like 1005 or 1006, are replaced with 1000.
+    pub close_code: u16,
+    /// The reason of the close frame sent on exit.
+    pub close_reason: String,
+}
+
+impl Default for ClientCfg {
+    fn default() -> Self {
+        Self {
+            url: "ws://127.0.0.1:8080".to_string(),
+            headers: Default::default(),
+            subprotocols: Vec::new(),
+            in_format: Format::default_in(),
+            out_format: Format::default_out(),
+            verbatim_actions: false,
+            max_messages: Some(100),
+            memory_messages: 10_000,
+            tick_rate: 4.0,
+            frame_rate: 30.0,
+            log_file: None,
+            record_file: None,
+            replay_file: None,
+            replay_pace: Default::default(),
+            connect_timeout: Duration::from_secs(10),
+            headless_wait: Duration::from_secs(1),
+            history_dir: None,
+            #[cfg(feature = "tls")]
+            tls_ca_file: None,
+            #[cfg(feature = "tls")]
+            tls_client_cert: None,
+            #[cfg(feature = "tls")]
+            tls_client_key: None,
+            #[cfg(feature = "tls")]
+            tls_insecure: false,
+            reconnect_delay: Duration::from_millis(500),
+            reconnect_max_delay: Duration::from_secs(30),
+            reconnect_factor: 2,
+            reconnect_jitter: Duration::from_millis(250),
+            ping_interval: Some(Duration::from_secs(15)),
+            idle_timeout: Some(Duration::from_secs(45)),
+            close_code: 1000,
+            close_reason: String::new(),
+        }
+    }
 }
 
 /// A client that starts a TUI app for communicating with a server
@@ -38,10 +236,39 @@ where
     Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
 {
     cfg: ClientCfg,
-    input: String,
-    msgs: VecDeque<String>,
+    codec: Arc<dyn Codec<Action, Event, Err>>,
+    /// Set once [`Client::codec`] replaced the codec of [`ClientCfg::out_format`].
+    custom_codec: bool,
+    correlate: Option<Arc<dyn Correlate<Action, Event, Err>>>,
+    tracker: correlate::Tracker,
+    editor: editor::Editor,
+    history: history::History,
+    search: Option<history::Search>,
+    /// How typed input is sent as a raw frame, parsed as an action if unset.
+    raw_mode: Option<raw::RawMode>,
+    msgs: store::Store,
+    log: Option<std::io::LineWriter<std::fs::File>>,
+    recorder: Option<recording::Recorder>,
+    replay: Option<replay::Replay>,
     state: State,
+    /// Set once the client cannot talk to the server anymore.
+    error: Option<ClientError>,
+    conn_info: Option<conn::ConnInfo>,
+    health: conn::Health,
+    show_conn_info: bool,
+    show_latency: bool,
+    detail: detail::Detail,
+    show_detail: bool,
+    prompt: Option<Prompt>,
+    /// The text searched for in the Events list.
+    find: String,
+    filter: Option<filter::Filter>,
+    /// The indices of the messages in memory passing the filter, in order.
+    visible: std::collections::VecDeque<usize>,
+    /// The index of the selected message among the visible ones.
     scroll_state: ratatui::widgets::ListState,
+    /// The TLS settings, built by [`Client::prepare`].
+    connector: conn::Connector,
     _phant: std::marker::PhantomData<(Action, Event, Err)>,
 }
 
@@ -52,230 +279,710 @@ where
     Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
 {
     /// Creates a new client.
+    ///
+    /// Frames are encoded and decoded with [`ClientCfg::out_format`] unless a codec is set
+    /// through [`Client::codec`].
     pub fn new(cfg: ClientCfg) -> Self {
         Self {
+            codec: Arc::new(cfg.out_format),
+            custom_codec: false,
+            correlate: None,
+            tracker: Default::default(),
+            msgs: store::Store::new(cfg.max_messages, cfg.memory_messages),
             cfg,
-            input: Default::default(),
-            msgs: Default::default(),
+            editor: Default::default(),
+            history: Default::default(),
+            search: None,
+            raw_mode: None,
+            log: None,
+            recorder: None,
+            replay: None,
             state: State::InputSelected,
+            error: None,
+            conn_info: None,
+            health: Default::default(),
+            show_conn_info: false,
+            show_latency: false,
+            detail: Default::default(),
+            show_detail: true,
+            prompt: None,
+            find: String::new(),
+            filter: None,
+            visible: Default::default(),
             scroll_state: Default::default(),
+            connector: Default::default(),
             _phant: Default::default(),
         }
     }
 
+    /// Sets the codec used for frames on the wire.
+    pub fn codec(mut self, codec: impl Codec<Action, Event, Err>) -> Self {
+        self.codec = Arc::new(codec);
+        self.custom_codec = true;
+        self
+    }
+
+    /// Sets how responses are linked to the actions they answer, enabling latency tracking.
+    ///
+    /// Takes a [`Correlate`] implementation or a pair of closures pulling the id out of an
+    /// action and out of a response. Responses are linked to an action until a minute after
+    /// its first response, and at most 10000 actions are remembered.
+    pub fn correlate(mut self, correlate: impl Correlate<Action, Event, Err>) -> Self {
+        self.correlate = Some(Arc::new(correlate));
+        self
+    }
+
     /// Starts the client.
-    pub async fn start(self) -> color_eyre::Result<()> {
-        color_eyre::install()?;
-
-        let (stream, _res) = tokio_tungstenite::connect_async(self.cfg.url.clone()).await?;
-        let (mut ws_tx, mut ws_rx) = stream.split();
-        let (res_tx, res_rx) = tokio::sync::mpsc::channel::<Res<Event, Err>>(100);
-        let (req_tx, mut req_rx) = tokio::sync::mpsc::channel::<Action>(100);
-        let (sys_tx, sys_rx) = tokio::sync::mpsc::channel::<String>(100);
-
-        // read ws messages
-        let read_ws_task =
-            tokio::spawn({
-                let sys_tx = sys_tx.clone();
-
-                async move {
-                    while let Some(msg) = ws_rx.next().await {
-                        match msg {
-                            Ok(msg) => {
-                                match msg {
-                                    tokio_tungstenite::tungstenite::Message::Text(text) => {
-                                        // TODO: support more message formats
-                                        #[cfg(feature = "out-json")]
-                                        let parse_res =
-                                            serde_json::from_str::<Res<Event, Err>>(&text);
-                                        #[cfg(feature = "out-ron")]
-                                        let parse_res = ron::from_str::<Res<Event, Err>>(&text);
-                                        #[cfg(not(any(feature = "out-json", feature = "out-ron")))]
-                                        let parse_res: Result<String, String> = unreachable!();
-
-                                        let res = match parse_res {
-                                            Ok(res) => res,
-                                            Err(err) => {
-                                                if let Err(_) = sys_tx.send(err.to_string()).await {
-                                                    break;
-                                                }
-                                                continue;
-                                            }
-                                        };
-                                        #[cfg(any(feature = "out-json", feature = "out-ron"))]
-                                        if let Err(_err) = res_tx.send(res).await {
-                                            break;
-                                        };
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            Err(err) => {
-                                if let Err(_) = sys_tx.send(err.to_string()).await {
-                                    break;
-                                }
-                                continue;
-                            }
-                        }
+    pub async fn start(mut self) -> color_eyre::Result<()> {
+        self.prepare()?;
+        if let Some(path) = &self.cfg.replay_file {
+            let replay = replay::Replay::load(path, self.cfg.replay_pace)?;
+            self.replay = Some(replay);
+            self.add_msg(record::MessageRecord::system(format!(
+                "replaying {} once connected",
+                path.display()
+            )));
+        }
+        if let Some(dir) = &self.cfg.history_dir {
+            match history::History::load(dir, &self.cfg.url) {
+                Ok(history) => self.history = history,
+                Err(err) => self.add_msg(record::MessageRecord::system(format!(
+                    "failed to load request history: {err}"
+                ))),
+            }
+        }
+
+        // keep the connection alive in the background
+        let mut link = self.link();
+
+        let mut tui = tui::Tui::new()?
+            .tick_rate(self.cfg.tick_rate)
+            .frame_rate(self.cfg.frame_rate)
+            .paste(true);
+        tui.enter()?;
+        let result = self.run(&mut tui, &mut link).await;
+        // restore the terminal before an error from `run` is reported
+        let exited = tui.exit();
+
+        link.shut_down().await;
+
+        result.and(exited)
+    }
+
+    /// Installs the hooks and opens the outputs, checking the url and the TLS settings
+    /// before connecting.
+    fn prepare(&mut self) -> color_eyre::Result<()> {
+        install_hooks()?;
+        if !self.cfg.in_format.is_in() {
+            color_eyre::eyre::bail!(
+                "{0} cannot be used for the input box without the `in-{0}` feature",
+                self.cfg.in_format
+            );
+        }
+        // a custom codec decides the wire format on its own
+        if !self.custom_codec && !self.cfg.out_format.is_out() {
+            color_eyre::eyre::bail!(
+                "{0} cannot be used on the wire without the `out-{0}` feature",
+                self.cfg.out_format
+            );
+        }
+        if self.cfg.verbatim_actions
+            && (self.cfg.in_format.is_binary()
+                || (!self.custom_codec && self.cfg.in_format != self.cfg.out_format))
+        {
+            color_eyre::eyre::bail!(
+                "verbatim actions need the same text format for the input box and the wire"
+            );
+        }
+        self.open_outputs()?;
+        // fail early on a malformed url or certificate instead of retrying it forever
+        conn::handshake_request(&self.cfg)?;
+        self.connector = conn::Connector::new(&self.cfg)?;
+
+        Ok(())
+    }
+
+    /// Spawns the connection task.
+    fn link(&self) -> conn::Link<Action> {
+        conn::Link::spawn(
+            &self.cfg,
+            self.connector.clone(),
+            self.codec.clone(),
+            self.correlate.clone(),
+        )
+    }
+
+    /// Opens the log file and the recording.
+    fn open_outputs(&mut self) -> color_eyre::Result<()> {
+        if let Some(path) = &self.cfg.log_file {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            self.log = Some(std::io::LineWriter::new(file));
+        }
+        if let Some(path) = &self.cfg.record_file {
+            self.recorder = Some(recording::Recorder::create(path, &self.cfg.url)?);
+        }
+
+        Ok(())
+    }
+
+    async fn run(mut self, tui: &mut Tui, link: &mut conn::Link<Action>) -> color_eyre::Result<()> {
+        loop {
+            self.render(tui)?;
+
+            while let Ok(msg) = link.msg_rx.try_recv() {
+                let report = self.replay.as_mut().and_then(|replay| replay.compare(&msg));
+                self.add_msg(msg);
+                if let Some(report) = report {
+                    self.add_msg(report);
+                }
+            }
+
+            if link.info_rx.has_changed().unwrap_or(false) {
+                self.conn_info = link.info_rx.borrow_and_update().clone();
+            }
+            if link.health_rx.has_changed().unwrap_or(false) {
+                self.health = link.health_rx.borrow_and_update().clone();
+            }
+
+            if self.error.is_none() {
+                if let Some(err) = link.stopped().await {
+                    if let ClientError::ConnectionPanicked(_) = err {
+                        // the panic hook restored the terminal to print the report
+                        tui.exit()?;
+                        tui.enter()?;
+                        tui.clear()?;
                     }
+                    self.add_msg(record::MessageRecord::new(
+                        record::MessageKind::Error,
+                        err.to_string(),
+                    ));
+                    // the task is gone before it could report it
+                    self.conn_info = None;
+                    self.health.state = conn::LinkState::Closed;
+                    self.error = Some(err);
                 }
-            });
+            }
 
-        // write ws messages
-        let write_ws_task = tokio::spawn(async move {
-            while let Some(req) = req_rx.recv().await {
-                #[cfg(feature = "out-json")]
-                let serialized = serde_json::to_string(&req);
-                #[cfg(feature = "out-ron")]
-                let serialized = ron::to_string(&req);
-                #[cfg(not(any(feature = "out-json", feature = "out-ron")))]
-                let serialized: Result<String, String> = unreachable!();
-
-                let msg = match serialized {
-                    Ok(msg) => msg,
-                    Err(err) => {
-                        if let Err(_) = sys_tx.send(err.to_string()).await {
-                            break;
+            let due = match (&mut self.replay, &self.conn_info) {
+                (Some(replay), Some(_)) => replay.due(std::time::Instant::now()),
+                _ => Vec::new(),
+            };
+            for msg in due {
+                self.send_replayed(msg, &link.req_tx).await;
+            }
+
+            if let Some(evt) = tui.next().await {
+                let quit = match (&self.state, evt) {
+                    (_, tui::Event::Key(key)) if self.error.is_some() => {
+                        self.on_error_key(key, link)
+                    }
+                    (_, tui::Event::Error) => {
+                        self.add_msg(record::MessageRecord::system(
+                            "failed to read terminal input",
+                        ));
+                        false
+                    }
+                    (_, tui::Event::Closed) => {
+                        break Err(color_eyre::eyre::eyre!("the terminal input closed"));
+                    }
+                    (State::InputSelected, tui::Event::Key(key)) => {
+                        self.on_input_key(key, &link.req_tx).await
+                    }
+                    (State::InputSelected, tui::Event::Paste(text)) => {
+                        // raw frames are sent exactly as pasted
+                        if self.raw_mode.is_some() {
+                            self.editor.insert_verbatim(&text);
+                        } else {
+                            self.editor.insert_str(&text);
                         }
-                        continue;
+                        false
+                    }
+                    (State::MsgListSelected, tui::Event::Key(key)) => {
+                        self.on_view_key(key, &link.req_tx).await
                     }
+                    _ => false,
                 };
-                if let Err(_err) = ws_tx
-                    .send(tokio_tungstenite::tungstenite::Message::Text(msg.into()))
-                    .await
-                {
-                    break;
+                if quit {
+                    break Ok(());
                 }
             }
-        });
-
-        let mut tui = tui::Tui::new()?
-            .tick_rate(4.0) // 4 ticks per second
-            .frame_rate(30.0); // 30 frames per second
-        tui.enter()?;
-        let result = self.run(&mut tui, req_tx, res_rx, sys_rx).await;
-        tui.exit()?;
+        }
+    }
 
-        read_ws_task.abort();
-        write_ws_task.abort();
+    /// Handles a key press while the client is in an error state, returning whether to quit.
+    fn on_error_key(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        link: &mut conn::Link<Action>,
+    ) -> bool {
+        match key.code {
+            crossterm::event::KeyCode::Esc | crossterm::event::KeyCode::Char('q') => return true,
+            crossterm::event::KeyCode::Char('r') => {
+                *link = self.link();
+                self.error = None;
+                self.conn_info = None;
+                self.health = Default::default();
+                self.add_msg(record::MessageRecord::system(
+                    "restarted the connection task",
+                ));
+            }
+            _ => {}
+        }
 
-        result
+        false
     }
 
-    async fn run(
-        mut self,
-        tui: &mut Tui,
-        req_tx: Sender<Action>,
-        mut res_rx: Receiver<Res<Event, Err>>,
-        mut sys_rx: Receiver<String>,
-    ) -> color_eyre::Result<()> {
-        loop {
-            self.render(tui)?;
+    /// Handles a key press in INPUT mode, returning whether to quit.
+    async fn on_input_key(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        req_tx: &Sender<conn::Outgoing<Action>>,
+    ) -> bool {
+        if self.search.is_some() {
+            self.on_search_key(key);
+            return false;
+        }
+
+        let ctrl = key
+            .modifiers
+            .contains(crossterm::event::KeyModifiers::CONTROL);
+        let newline = key.modifiers.intersects(
+            crossterm::event::KeyModifiers::SHIFT | crossterm::event::KeyModifiers::ALT,
+        );
+        match key.code {
+            crossterm::event::KeyCode::Esc => return true,
+            crossterm::event::KeyCode::Tab => self.state = State::MsgListSelected,
+            crossterm::event::KeyCode::Enter if !newline => {
+                if self.editor.is_empty() {
+                    return false;
+                }
 
-            while let Ok(res) = res_rx.try_recv() {
-                match res {
-                    Ok(res) => {
-                        self.add_msg(format!("received: {:?}", res));
+                let input = self.editor.text();
+                if let Err(err) = self.history.push(input.clone()) {
+                    self.add_msg(record::MessageRecord::system(format!(
+                        "failed to save request history: {err}"
+                    )));
+                }
+                let req = match self.raw_mode {
+                    Some(mode) => mode.outgoing(&input).map_err(|err| {
+                        format!("invalid raw {} frame: {input}: {err}", mode.label())
+                    }),
+                    None => {
+                        conn::Outgoing::parse(self.cfg.in_format, self.cfg.verbatim_actions, &input)
+                            .map_err(|err| format!("invalid request format: {input}: {err}"))
                     }
+                };
+                match req {
+                    Ok(req) => self.send(req_tx, req).await,
                     Err(err) => {
-                        self.add_msg(format!("error: {:?}", err));
+                        self.add_msg(record::MessageRecord::new(record::MessageKind::Error, err))
                     }
                 }
+                self.editor.clear();
+            }
+            crossterm::event::KeyCode::Char('t') if ctrl => {
+                self.raw_mode = raw::RawMode::cycle(self.raw_mode);
+            }
+            crossterm::event::KeyCode::Char('r') if ctrl => {
+                let mut search = history::Search::default();
+                search.restart(&self.history);
+                self.search = Some(search);
+            }
+            _ => {
+                if self.editor.handle_key(key) {
+                    return false;
+                }
+
+                // the editor leaves Up and Down at its edges to walk the history
+                let recalled = match key.code {
+                    crossterm::event::KeyCode::Up => self.history.prev(&self.editor.text()),
+                    crossterm::event::KeyCode::Down => self.history.next(),
+                    _ => None,
+                };
+                if let Some(recalled) = recalled {
+                    self.editor.set_text(recalled);
+                }
             }
+        }
+
+        false
+    }
 
-            while let Ok(msg) = sys_rx.try_recv() {
-                self.add_msg(format!("internal message: {}", msg));
+    /// Handles a key press during a reverse history search.
+    fn on_search_key(&mut self, key: crossterm::event::KeyEvent) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+
+        let ctrl = key
+            .modifiers
+            .contains(crossterm::event::KeyModifiers::CONTROL);
+        match key.code {
+            crossterm::event::KeyCode::Char('r') if ctrl => search.older(&self.history),
+            crossterm::event::KeyCode::Char('g') if ctrl => self.search = None,
+            crossterm::event::KeyCode::Esc => self.search = None,
+            crossterm::event::KeyCode::Enter => {
+                if let Some(entry) = search.found.and_then(|idx| self.history.get(idx)) {
+                    self.editor.set_text(entry);
+                }
+                self.search = None;
+            }
+            crossterm::event::KeyCode::Backspace => {
+                search.query.pop();
+                search.restart(&self.history);
+            }
+            crossterm::event::KeyCode::Char(ch) if !ctrl => {
+                search.query.push(ch);
+                search.restart(&self.history);
             }
+            _ => {}
+        }
+    }
 
-            if let Some(evt) = tui.next().await {
-                match self.state {
-                    State::InputSelected => match evt {
-                        tui::Event::Key(evt) => match evt.code {
-                            crossterm::event::KeyCode::Esc => break Ok(()),
-                            crossterm::event::KeyCode::Tab => self.state = State::MsgListSelected,
-                            crossterm::event::KeyCode::Enter => {
-                                if !self.input.is_empty() {
-                                    self.add_msg(format!("sent: {}", self.input.clone()));
-                                    #[cfg(feature = "in-json")]
-                                    let parse_res = serde_json::from_str::<Action>(&self.input);
-                                    #[cfg(feature = "in-ron")]
-                                    let parse_res = ron::from_str::<Action>(&self.input);
-                                    #[cfg(not(any(feature = "in-json", feature = "in-ron")))]
-                                    let parse_res: Result<
-                                        Res<Event, Err>,
-                                        String,
-                                    > = unreachable!();
-
-                                    let Ok(req) = parse_res else {
-                                        self.add_msg(format!(
-                                            "error: invalid request format: {}",
-                                            self.input
-                                        ));
-                                        self.input.clear();
-                                        continue;
-                                    };
-                                    #[cfg(any(feature = "in-json", feature = "in-ron"))]
-                                    req_tx
-                                        .send(req)
-                                        .await
-                                        .expect("request channel should not be closed");
-                                    self.input.clear();
-                                }
-                            }
-                            crossterm::event::KeyCode::Backspace => _ = self.input.pop(),
-                            crossterm::event::KeyCode::Char(ch) => self.input.push(ch),
-                            _ => {}
-                        },
-                        _ => {}
-                    },
-                    State::MsgListSelected => match evt {
-                        tui::Event::Key(evt) => match evt.code {
-                            crossterm::event::KeyCode::Esc => break Ok(()),
-                            crossterm::event::KeyCode::Backspace => {
-                                if evt
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::SHIFT)
-                                {
-                                    self.input.clear();
-                                    self.add_msg("cleared input box".to_string());
-                                }
-                            }
-                            crossterm::event::KeyCode::Tab => self.state = State::InputSelected,
-                            crossterm::event::KeyCode::Char('j') => {
-                                match self.scroll_state.selected() {
-                                    Some(idx) => {
-                                        if idx < self.msgs.len().saturating_sub(1) {
-                                            self.scroll_state.select(Some(idx + 1));
-                                        }
-                                    }
-                                    None => {
-                                        if !self.msgs.is_empty() {
-                                            self.scroll_state.select(Some(0))
-                                        }
-                                    }
-                                }
-                            }
-                            crossterm::event::KeyCode::Char('k') => {
-                                match self.scroll_state.selected() {
-                                    Some(idx) => {
-                                        if idx > 0 {
-                                            self.scroll_state.select(Some(idx - 1));
-                                        }
-                                    }
-                                    None => {
-                                        if !self.msgs.is_empty() {
-                                            self.scroll_state.select(Some(0))
-                                        }
-                                    }
-                                }
-                            }
-                            _ => {}
-                        },
-                        _ => {}
-                    },
+    /// Handles a key press in VIEW mode, returning whether to quit.
+    async fn on_view_key(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        req_tx: &Sender<conn::Outgoing<Action>>,
+    ) -> bool {
+        if self.prompt.is_some() {
+            self.on_prompt_key(key);
+            return false;
+        }
+
+        match key.code {
+            crossterm::event::KeyCode::Esc => return true,
+            crossterm::event::KeyCode::Backspace
+                if key
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::SHIFT) =>
+            {
+                self.editor.clear();
+                self.add_msg(record::MessageRecord::system("cleared input box"));
+            }
+            crossterm::event::KeyCode::Tab => self.state = State::InputSelected,
+            crossterm::event::KeyCode::Char('i') => self.show_conn_info = !self.show_conn_info,
+            crossterm::event::KeyCode::Char('L') => self.show_latency = !self.show_latency,
+            crossterm::event::KeyCode::Char('c') => self.select_correlated(),
+            crossterm::event::KeyCode::Char('d') => self.show_detail = !self.show_detail,
+            crossterm::event::KeyCode::Char('J') => self.detail.down(1),
+            crossterm::event::KeyCode::Char('K') => self.detail.up(1),
+            crossterm::event::KeyCode::PageDown => self.detail.down(self.detail.page()),
+            crossterm::event::KeyCode::PageUp => self.detail.up(self.detail.page()),
+            crossterm::event::KeyCode::Char('t') => self.detail.toggle_tree(),
+            crossterm::event::KeyCode::Char(' ') => self.detail.toggle(),
+            crossterm::event::KeyCode::Right => self.detail.expand(),
+            crossterm::event::KeyCode::Left => self.detail.collapse(),
+            crossterm::event::KeyCode::Char('/') => {
+                self.find.clear();
+                self.prompt = Some(Prompt::Find(String::new()));
+            }
+            crossterm::event::KeyCode::Char('n') => self.find_next(true, false),
+            crossterm::event::KeyCode::Char('N') => self.find_next(false, false),
+            crossterm::event::KeyCode::Char('f') => {
+                let expr = self
+                    .filter
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                self.prompt = Some(Prompt::Filter(expr));
+            }
+            crossterm::event::KeyCode::Char('R') => self.toggle_recording(),
+            crossterm::event::KeyCode::Char('>') => {
+                if let Some(msg) = self.replay.as_mut().and_then(replay::Replay::step) {
+                    self.send_replayed(msg, req_tx).await;
                 }
             }
+            crossterm::event::KeyCode::Char('G') => {
+                self.page(store::Store::page_end);
+                let len = self.visible.len();
+                if len > 0 {
+                    self.scroll_state.select(Some(len - 1));
+                    self.detail.reset();
+                }
+            }
+            crossterm::event::KeyCode::Char('j') => {
+                // page newer messages in at the bottom of the window
+                if self
+                    .scroll_state
+                    .selected()
+                    .is_some_and(|idx| idx + 1 >= self.visible.len())
+                {
+                    self.page(store::Store::page_down);
+                }
+                self.select_next();
+            }
+            crossterm::event::KeyCode::Char('k') => {
+                // page older messages in at the top of the window
+                if self.scroll_state.selected() == Some(0) {
+                    self.page(store::Store::page_up);
+                }
+                self.select_prev();
+            }
+            _ => {}
         }
+
+        false
+    }
+
+    /// Sends a recorded action again.
+    async fn send_replayed(
+        &mut self,
+        msg: record::MessageRecord,
+        req_tx: &Sender<conn::Outgoing<Action>>,
+    ) {
+        let Some(raw) = &msg.raw else {
+            return;
+        };
+        let frame = raw.to_message();
+        self.send(req_tx, conn::Outgoing::Frame(frame, Box::new(msg)))
+            .await;
+    }
+
+    /// Hands a request to the connection task, reporting it if the task is gone.
+    async fn send(&mut self, req_tx: &Sender<conn::Outgoing<Action>>, req: conn::Outgoing<Action>) {
+        if let Err(err) = req_tx.send(req).await {
+            self.add_msg(record::MessageRecord::new(
+                record::MessageKind::Error,
+                format!("the connection task stopped, dropped request: {}", err.0),
+            ));
+        }
+    }
+
+    /// Starts recording the session to a new file or stops the running recording.
+    fn toggle_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            self.add_msg(record::MessageRecord::system(format!(
+                "stopped recording to {}",
+                recorder.path().display()
+            )));
+            return;
+        }
+
+        // a fresh file every time, so restarting never overwrites an earlier recording
+        match recording::Recorder::create_new(&self.cfg.url) {
+            Ok(recorder) => {
+                let msg = format!("recording to {}", recorder.path().display());
+                self.recorder = Some(recorder);
+                self.add_msg(record::MessageRecord::system(msg));
+            }
+            Err(err) => self.add_msg(record::MessageRecord::system(format!(
+                "failed to start recording: {err}"
+            ))),
+        }
+    }
+
+    /// Selects the next visible message.
+    fn select_next(&mut self) {
+        match self.scroll_state.selected() {
+            Some(idx) => {
+                if idx < self.visible.len().saturating_sub(1) {
+                    self.scroll_state.select(Some(idx + 1));
+                    self.detail.reset();
+                }
+            }
+            None => {
+                if !self.visible.is_empty() {
+                    self.scroll_state.select(Some(0))
+                }
+            }
+        }
+    }
+
+    /// Selects the previous visible message.
+    fn select_prev(&mut self) {
+        match self.scroll_state.selected() {
+            Some(idx) => {
+                if idx > 0 {
+                    self.scroll_state.select(Some(idx - 1));
+                    self.detail.reset();
+                }
+            }
+            None => {
+                if !self.visible.is_empty() {
+                    self.scroll_state.select(Some(0))
+                }
+            }
+        }
+    }
+
+    /// Selects the action the selected response answers, or the first response to the
+    /// selected action.
+    fn select_correlated(&mut self) {
+        let Some(selected) = self.selected() else {
+            return;
+        };
+        let target = match self.msgs[selected].response_to {
+            Some(request) => Some(request),
+            None => self
+                .msgs
+                .iter()
+                .find(|(_, msg)| msg.response_to == Some(selected))
+                .map(|(idx, _)| idx),
+        };
+
+        if let Some(idx) =
+            target.and_then(|target| self.visible.iter().position(|&idx| idx == target))
+        {
+            self.scroll_state.select(Some(idx));
+            self.detail.reset();
+        }
+    }
+
+    /// Handles a key press while typing a search or filter.
+    fn on_prompt_key(&mut self, key: crossterm::event::KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        let text = match prompt {
+            Prompt::Find(text) | Prompt::Filter(text) => text,
+        };
+
+        match key.code {
+            crossterm::event::KeyCode::Esc => {
+                if let Some(Prompt::Find(_)) = self.prompt.take() {
+                    self.find.clear();
+                }
+            }
+            crossterm::event::KeyCode::Enter => {
+                // the search is already applied while typing
+                if let Some(Prompt::Filter(expr)) = self.prompt.take() {
+                    self.set_filter(&expr);
+                }
+            }
+            crossterm::event::KeyCode::Backspace => {
+                text.pop();
+            }
+            crossterm::event::KeyCode::Char(ch) => text.push(ch),
+            _ => {}
+        }
+
+        if let Some(Prompt::Find(text)) = &self.prompt {
+            if *text != self.find {
+                self.find = text.clone();
+                self.find_next(true, true);
+            }
+        }
+    }
+
+    /// Replaces the filter, keeping the selected message selected if it is still visible.
+    fn set_filter(&mut self, expr: &str) {
+        let selected = self.selected();
+        if expr.trim().is_empty() {
+            self.filter = None;
+        } else {
+            match expr.parse() {
+                Ok(filter) => self.filter = Some(filter),
+                Err(err) => {
+                    self.add_msg(record::MessageRecord::system(format!(
+                        "invalid filter: {err}"
+                    )));
+                    return;
+                }
+            }
+        }
+
+        self.refresh_visible();
+        self.reselect(selected);
+    }
+
+    /// Moves the window of the message history, keeping the selected message selected.
+    fn page<T>(&mut self, page: impl FnOnce(&mut store::Store) -> std::io::Result<T>) {
+        let selected = self.selected();
+        if let Err(err) = page(&mut self.msgs) {
+            self.add_msg(record::MessageRecord::system(format!(
+                "failed to read old messages from disk: {err}"
+            )));
+        }
+        self.refresh_visible();
+        self.reselect(selected);
+    }
+
+    /// Selects the message by its index, or the next visible one if it is hidden or gone.
+    fn reselect(&mut self, selected: Option<usize>) {
+        let idx =
+            selected.and_then(|selected| self.visible.iter().position(|&idx| idx >= selected));
+        self.scroll_state.select(idx);
+        if self.selected() != selected {
+            self.detail.reset();
+        }
+    }
+
+    /// Selects the next or previous visible message matching the search, wrapping around.
+    fn find_next(&mut self, forward: bool, include_selected: bool) {
+        if self.find.is_empty() {
+            return;
+        }
+
+        let len = self.visible.len();
+        let (start, skip) = match self.scroll_state.selected() {
+            Some(idx) => (idx, usize::from(!include_selected)),
+            None => (0, 0),
+        };
+        for offset in skip..skip + len {
+            let idx = if forward {
+                (start + offset) % len
+            } else {
+                (start + len - offset % len) % len
+            };
+            let line = self.msgs[self.visible[idx]].to_string();
+            if !filter::find(&line, &self.find).is_empty() {
+                self.scroll_state.select(Some(idx));
+                self.detail.reset();
+                return;
+            }
+        }
+    }
+
+    /// Returns whether the message passes the filter.
+    fn passes(&self, msg: &record::MessageRecord) -> bool {
+        match &self.filter {
+            Some(filter) => filter.matches(msg),
+            None => true,
+        }
+    }
+
+    /// Rebuilds the indices of the messages in memory passing the filter.
+    fn refresh_visible(&mut self) {
+        let visible = self
+            .msgs
+            .iter()
+            .filter(|(_, msg)| self.passes(msg))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.visible = visible;
+    }
+
+    /// Catches the visible messages up with a push, returning how many of them were moved
+    /// out of memory.
+    fn sync_visible(&mut self) -> usize {
+        let start = self.msgs.start();
+        let mut dropped = 0;
+        while self.visible.front().is_some_and(|&idx| idx < start) {
+            self.visible.pop_front();
+            dropped += 1;
+        }
+
+        // the message only lands in memory if the window follows the newest ones
+        let newest = self.msgs.total() - 1;
+        if self.msgs.is_at_end()
+            && self.msgs.len() > 0
+            && self.visible.back() != Some(&newest)
+            && self.passes(&self.msgs[newest])
+        {
+            self.visible.push_back(newest);
+        }
+
+        dropped
+    }
+
+    /// Returns the index of the selected message.
+    fn selected(&self) -> Option<usize> {
+        let idx = self.scroll_state.selected()?;
+        self.visible.get(idx).copied()
     }
 
     fn render(&mut self, tui: &mut Tui) -> color_eyre::Result<()> {
@@ -283,24 +990,58 @@ where
             // wedge
             let [help_area, input_area, msgs_area] = ratatui::layout::Layout::vertical([
                 ratatui::layout::Constraint::Length(1),
-                ratatui::layout::Constraint::Length(3),
+                ratatui::layout::Constraint::Length(self.editor.height()),
                 ratatui::layout::Constraint::Min(1),
             ])
             .areas(f.area());
 
-            let widget = ratatui::widgets::Paragraph::new(match self.state {
-                State::InputSelected => format!("In INPUT mode"),
-                State::MsgListSelected => format!(
-                    "In VIEW mode | Selected {:?} message",
+            let mut help = match (&self.state, &self.prompt) {
+                (State::InputSelected, _) => {
+                    "In INPUT mode | Enter: send | Shift+Enter: newline | Up/Down: history | Ctrl+R: search history | Ctrl+T: raw mode".to_string()
+                }
+                (State::MsgListSelected, Some(Prompt::Find(text))) => {
+                    format!("Search: {text}_ | Enter: confirm | Esc: cancel")
+                }
+                (State::MsgListSelected, Some(Prompt::Filter(text))) => format!(
+                    "Filter: {text}_ | Enter: apply, empty to clear | Esc: cancel | kind:sent,received,error,system variant:Name /regex/ !term text"
+                ),
+                (State::MsgListSelected, None) => format!(
+                    "In VIEW mode | Selected {:?} message | G: newest | J/K, PgUp/PgDn: scroll detail | d: detail | t: tree | Space/Left/Right: fold | /: search | n/N: next/prev match | f: filter | R: record | i: connection info",
                     self.scroll_state.selected()
                 ),
-            });
+            };
+            if let Some(filter) = &self.filter {
+                help = format!("[filter: {filter}] {help}");
+            }
+            if self.recorder.is_some() {
+                help = format!("[recording] {help}");
+            }
+            if let Some(replay) = &self.replay {
+                help = format!("[{}] {help}", replay.status());
+                if !replay.is_done() {
+                    help.push_str(" | >: send next replayed action");
+                }
+            }
+            help = format!("[{}] {help}", self.health);
+            if self.correlate.is_some() && matches!(self.state, State::MsgListSelected) {
+                help.push_str(" | c: jump to request/response | L: latency");
+            }
+            let widget = match &self.error {
+                Some(err) => ratatui::widgets::Paragraph::new(format!(
+                    "{err} | r: restart the connection | Esc/q: quit"
+                ))
+                .style(ratatui::style::Style::default().fg(ratatui::style::Color::Red)),
+                None => ratatui::widgets::Paragraph::new(help),
+            };
             f.render_widget(widget, help_area);
 
             let block = {
                 let block = ratatui::widgets::Block::default()
                     .borders(ratatui::widgets::Borders::all())
-                    .title("Input");
+                    .title(match self.raw_mode {
+                        Some(mode) => format!("Input (raw {})", mode.label()),
+                        None => "Input".to_string(),
+                    });
                 let block = if matches!(self.state, State::InputSelected) {
                     block.border_style(
                         ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
@@ -311,13 +1052,120 @@ where
 
                 block
             };
-            let widget = ratatui::widgets::Paragraph::new(self.input.clone()).block(block);
-            f.render_widget(widget, input_area);
+            if let Some(search) = &self.search {
+                let found = search
+                    .found
+                    .and_then(|idx| self.history.get(idx))
+                    .unwrap_or_default();
+                let widget = ratatui::widgets::Paragraph::new(found)
+                    .block(block.title(format!("reverse search: {}", search.query)));
+                f.render_widget(widget, input_area);
+            } else {
+                self.editor.render(
+                    f,
+                    input_area,
+                    block,
+                    matches!(self.state, State::InputSelected),
+                );
+            }
+
+            let msgs_area = if self.show_conn_info {
+                let lines = match &self.conn_info {
+                    Some(info) => {
+                        let mut lines = vec![
+                            format!("url: {}", info.url),
+                            format!(
+                                "subprotocol: {}",
+                                info.subprotocol.as_deref().unwrap_or("none")
+                            ),
+                            "response headers:".to_string(),
+                        ];
+                        lines.extend(
+                            info.headers
+                                .iter()
+                                .map(|(name, value)| format!("  {name}: {value}")),
+                        );
+                        lines
+                    }
+                    None => vec!["not connected".to_string()],
+                };
+                let [info_area, msgs_area] = ratatui::layout::Layout::vertical([
+                    ratatui::layout::Constraint::Length(lines.len() as u16 + 2),
+                    ratatui::layout::Constraint::Min(1),
+                ])
+                .areas(msgs_area);
+
+                let block = ratatui::widgets::Block::default()
+                    .borders(ratatui::widgets::Borders::all())
+                    .title("Connection");
+                let widget = ratatui::widgets::Paragraph::new(lines.join("\n")).block(block);
+                f.render_widget(widget, info_area);
+
+                msgs_area
+            } else {
+                msgs_area
+            };
+
+            let msgs_area = if self.show_latency {
+                let mut lines = self.tracker.summary();
+                if lines.is_empty() {
+                    lines.push(match self.correlate {
+                        Some(_) => "no responses yet",
+                        None => "no correlation set, see Client::correlate",
+                    });
+                }
+                let [latency_area, msgs_area] = ratatui::layout::Layout::vertical([
+                    ratatui::layout::Constraint::Length(lines.len() as u16 + 2),
+                    ratatui::layout::Constraint::Min(1),
+                ])
+                .areas(msgs_area);
+
+                let block = ratatui::widgets::Block::default()
+                    .borders(ratatui::widgets::Borders::all())
+                    .title("Latency");
+                let widget = ratatui::widgets::Paragraph::new(lines.join("\n")).block(block);
+                f.render_widget(widget, latency_area);
+
+                msgs_area
+            } else {
+                msgs_area
+            };
+
+            let selected = self
+                .scroll_state
+                .selected()
+                .and_then(|idx| self.visible.get(idx))
+                .map(|&idx| (idx, &self.msgs[idx]));
+            let msgs_area = match selected {
+                Some(msg) if self.show_detail => {
+                    let [msgs_area, detail_area] = ratatui::layout::Layout::horizontal([
+                        ratatui::layout::Constraint::Percentage(50),
+                        ratatui::layout::Constraint::Percentage(50),
+                    ])
+                    .areas(msgs_area);
+                    self.detail.render(f, detail_area, msg, self.codec.format());
+
+                    msgs_area
+                }
+                _ => msgs_area,
+            };
 
             let block = {
+                let mut title = "Events".to_string();
+                if self.msgs.len() < self.msgs.total() - self.msgs.first() {
+                    title.push_str(&format!(
+                        " {}-{} of {}",
+                        self.msgs.start() + 1,
+                        self.msgs.start() + self.msgs.len(),
+                        self.msgs.total()
+                    ));
+                }
+                if self.filter.is_some() {
+                    title.push_str(&format!(" ({} shown)", self.visible.len()));
+                }
                 let block = ratatui::widgets::Block::default()
                     .borders(ratatui::widgets::Borders::all())
-                    .title("Events");
+                    .title(title);
                 let block = if matches!(self.state, State::MsgListSelected) {
                     block.border_style(
                         ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
@@ -328,17 +1176,55 @@ where
 
                 block
             };
-            let msgs = self
-                .msgs
-                .iter()
-                .cloned()
-                .map(|msg| ratatui::widgets::ListItem::new(msg));
+            // only the rows in view are laid out, memory may hold thousands of messages
+            let height = usize::from(msgs_area.height.saturating_sub(2)).max(1);
+            let mut offset = self.scroll_state.offset();
+            if let Some(selected) = self.scroll_state.selected() {
+                offset = offset.min(selected).max((selected + 1).saturating_sub(height));
+            }
+            let offset = offset.min(self.visible.len().saturating_sub(1));
+            let end = (offset + height).min(self.visible.len());
+            let msgs = self.visible.range(offset..end).map(|&idx| {
+                let msg = &self.msgs[idx];
+                let style = match msg.kind {
+                    record::MessageKind::Error => {
+                        ratatui::style::Style::default().fg(ratatui::style::Color::Red)
+                    }
+                    record::MessageKind::System => {
+                        ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray)
+                    }
+                    record::MessageKind::Sent if msg.raw_send => {
+                        ratatui::style::Style::default().fg(ratatui::style::Color::Magenta)
+                    }
+                    _ => ratatui::style::Style::default(),
+                };
+                let line = msg.to_string();
+                let mut spans = Vec::new();
+                let mut end = 0;
+                for range in filter::find(&line, &self.find) {
+                    spans.push(ratatui::text::Span::raw(line[end..range.start].to_string()));
+                    spans.push(ratatui::text::Span::styled(
+                        line[range.clone()].to_string(),
+                        ratatui::style::Style::default()
+                            .add_modifier(ratatui::style::Modifier::REVERSED),
+                    ));
+                    end = range.end;
+                }
+                spans.push(ratatui::text::Span::raw(line[end..].to_string()));
+                ratatui::widgets::ListItem::new(ratatui::text::Line::from(spans)).style(style)
+            });
             let widget = ratatui::widgets::List::new(msgs)
                 .block(block)
                 .highlight_style(
                     ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
                 );
-            f.render_stateful_widget(widget, msgs_area, &mut self.scroll_state);
+            let mut state = ratatui::widgets::ListState::default().with_selected(
+                self.scroll_state
+                    .selected()
+                    .map(|idx| idx.saturating_sub(offset)),
+            );
+            f.render_stateful_widget(widget, msgs_area, &mut state);
+            *self.scroll_state.offset_mut() = offset;
         })?;
 
         Ok(())
@@ -351,11 +1237,54 @@ where
     Res: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
     Err: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
 {
-    fn add_msg(&mut self, msg: String) {
-        if self.msgs.len() >= MAX_MESSAGES {
-            self.msgs.pop_front();
+    fn add_msg(&mut self, mut msg: record::MessageRecord) {
+        self.tracker.track(&mut msg, self.msgs.total());
+        let notice = self.write_msg(&msg);
+
+        let pushed = self.msgs.push(msg);
+        // keep the selected message selected without going over the whole window
+        let dropped = self.sync_visible();
+        if let Some(idx) = self.scroll_state.selected() {
+            match idx.checked_sub(dropped) {
+                Some(idx) => self.scroll_state.select(Some(idx)),
+                // it was moved out of memory, so select the oldest one left
+                None => {
+                    self.scroll_state
+                        .select((!self.visible.is_empty()).then_some(0));
+                    self.detail.reset();
+                }
+            }
+        }
+        if let Err(err) = pushed {
+            self.add_msg(record::MessageRecord::system(format!(
+                "failed to move old messages to disk, dropping them instead: {err}"
+            )));
+        }
+
+        if let Some(notice) = notice {
+            self.add_msg(notice);
+        }
+    }
+
+    /// Writes a message to the log file and the recording, returning a notice if that failed.
+    fn write_msg(&mut self, msg: &record::MessageRecord) -> Option<record::MessageRecord> {
+        if let Some(log) = &mut self.log {
+            if let Err(err) = writeln!(log, "{msg}") {
+                self.log = None;
+                return Some(record::MessageRecord::system(format!(
+                    "failed to write to log file, logging disabled: {err}"
+                )));
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.write(msg) {
+                self.recorder = None;
+                return Some(record::MessageRecord::system(format!(
+                    "failed to write to recording, recording stopped: {err}"
+                )));
+            }
         }
 
-        self.msgs.push_back(msg);
+        None
     }
 }
@@ -0,0 +1,306 @@
+//! A general-purpose client for any wire server.
+//!
+//! Actions, events and errors are handled as untyped values, so no protocol types are
+//! needed at compile time.
+
+use clap::Parser;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::http::{HeaderMap, HeaderName, HeaderValue};
+
+/// A TUI client for talking to wire servers.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about,
+    after_help = "Installed with `cargo install wire-cli --features cli`, adding `tls` for wss:// \
+                  urls and `in-*`/`out-*` features for more formats, see the README."
+)]
+struct Cli {
+    /// The WebSocket url of the server.
+    #[arg(env = "WIRE_CLI_URL")]
+    url: String,
+    /// An extra handshake header, as `name: value`. Can be repeated, or given one per line
+    /// in the environment variable.
+    #[arg(
+        short = 'H',
+        long = "header",
+        env = "WIRE_CLI_HEADER",
+        value_delimiter = '\n',
+        value_parser = parse_header
+    )]
+    headers: Vec<(HeaderName, HeaderValue)>,
+    /// A subprotocol offered to the server. Can be repeated, in order of preference.
+    #[arg(
+        long = "subprotocol",
+        env = "WIRE_CLI_SUBPROTOCOLS",
+        value_delimiter = ','
+    )]
+    subprotocols: Vec<String>,
+    /// The format of the requests typed into the input box.
+    #[arg(long = "in", env = "WIRE_CLI_IN", value_parser = parse_in_format, default_value_t = wire_cli::Format::default_in())]
+    in_format: wire_cli::Format,
+    /// The format of the frames sent to and received from the server.
+    #[arg(long = "out", env = "WIRE_CLI_OUT", value_parser = parse_out_format, default_value_t = wire_cli::Format::default_out())]
+    out_format: wire_cli::Format,
+    /// The maximum number of messages kept in the message history, 0 for unbounded.
+    #[arg(long, env = "WIRE_CLI_HISTORY_SIZE", default_value_t = 100)]
+    history_size: usize,
+    /// The number of messages kept in memory before older ones are moved to a temporary file.
+    #[arg(long, env = "WIRE_CLI_MEMORY_SIZE", default_value_t = 10_000)]
+    memory_size: usize,
+    /// The number of ticks per second.
+    #[arg(long, env = "WIRE_CLI_TICK_RATE", default_value_t = 4.0, value_parser = parse_rate)]
+    tick_rate: f64,
+    /// The number of frames rendered per second.
+    #[arg(long, env = "WIRE_CLI_FRAME_RATE", default_value_t = 30.0, value_parser = parse_rate)]
+    frame_rate: f64,
+    /// A file every line of the message history is appended to.
+    #[arg(long, env = "WIRE_CLI_LOG_FILE")]
+    log_file: Option<std::path::PathBuf>,
+    /// A file the session is recorded to, as NDJSON or as RON lines if it ends in `.ron`.
+    #[arg(long = "record", env = "WIRE_CLI_RECORD")]
+    record_file: Option<std::path::PathBuf>,
+    /// A recording whose actions are sent again once connected.
+    #[arg(long = "replay", env = "WIRE_CLI_REPLAY")]
+    replay_file: Option<std::path::PathBuf>,
+    /// The speed the recording is replayed at, relative to the recorded timing.
+    #[arg(
+        long,
+        env = "WIRE_CLI_REPLAY_SPEED",
+        default_value_t = 1.0,
+        value_parser = parse_speed,
+        requires = "replay_file"
+    )]
+    replay_speed: f64,
+    /// Sends the recorded actions one at a time with `>` instead of with their timing.
+    #[arg(long, env = "WIRE_CLI_REPLAY_STEP", requires = "replay_file")]
+    replay_step: bool,
+    /// Runs without a terminal UI, sending the actions read from stdin one per line and
+    /// printing the received events to stdout as JSON lines.
+    #[arg(long, env = "WIRE_CLI_HEADLESS")]
+    headless: bool,
+    /// A script file read instead of stdin, see `SCRIPTS.md`. Implies `--headless`.
+    #[arg(long, env = "WIRE_CLI_SCRIPT")]
+    script: Option<std::path::PathBuf>,
+    /// The seconds to wait for the connection in headless mode.
+    #[arg(long, env = "WIRE_CLI_CONNECT_TIMEOUT", default_value = "10", value_parser = parse_secs)]
+    connect_timeout: Duration,
+    /// The seconds to keep printing events after the last action in headless mode.
+    #[arg(long, env = "WIRE_CLI_WAIT", default_value = "1", value_parser = parse_secs)]
+    wait: Duration,
+    /// The seconds between pings measuring the round-trip time, 0 to never ping.
+    #[arg(long, env = "WIRE_CLI_PING_INTERVAL", default_value = "15", value_parser = parse_secs)]
+    ping_interval: Duration,
+    /// The seconds without any frame after which the connection counts as dead, 0 to wait
+    /// forever. Only applies while pinging.
+    #[arg(long, env = "WIRE_CLI_IDLE_TIMEOUT", default_value = "45", value_parser = parse_secs)]
+    idle_timeout: Duration,
+    /// The code of the close frame sent on exit, e.g. 1000 for a normal closure or 1001
+    /// for going away.
+    #[arg(long, env = "WIRE_CLI_CLOSE_CODE", default_value_t = 1000, value_parser = parse_close_code)]
+    close_code: u16,
+    /// The reason of the close frame sent on exit.
+    #[arg(long, env = "WIRE_CLI_CLOSE_REASON", default_value = "")]
+    close_reason: String,
+    /// The directory sent requests are saved in, one file per url.
+    #[arg(long, env = "WIRE_CLI_HISTORY_DIR")]
+    history_dir: Option<std::path::PathBuf>,
+    /// Does not save sent requests to disk.
+    #[arg(long, env = "WIRE_CLI_NO_HISTORY", conflicts_with = "history_dir")]
+    no_history: bool,
+    /// A PEM bundle of extra CA certificates to trust.
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "WIRE_CLI_CA_FILE")]
+    ca_file: Option<std::path::PathBuf>,
+    /// A PEM client certificate chain presented for mutual TLS.
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "WIRE_CLI_CLIENT_CERT", requires = "client_key")]
+    client_cert: Option<std::path::PathBuf>,
+    /// The PEM private key of the client certificate.
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "WIRE_CLI_CLIENT_KEY", requires = "client_cert")]
+    client_key: Option<std::path::PathBuf>,
+    /// Skips verifying the server certificate. Only for local testing.
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "WIRE_CLI_INSECURE", conflicts_with = "ca_file")]
+    insecure: bool,
+}
+
+impl Cli {
+    /// Converts the arguments into a client config.
+    fn into_cfg(self) -> wire_cli::ClientCfg {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            headers.append(name, value);
+        }
+
+        let mut cfg = wire_cli::ClientCfg {
+            url: self.url,
+            headers,
+            subprotocols: self.subprotocols,
+            in_format: self.in_format,
+            out_format: self.out_format,
+            max_messages: (self.history_size > 0).then_some(self.history_size),
+            memory_messages: self.memory_size,
+            tick_rate: self.tick_rate,
+            frame_rate: self.frame_rate,
+            log_file: self.log_file,
+            record_file: self.record_file,
+            replay_file: self.replay_file,
+            connect_timeout: self.connect_timeout,
+            headless_wait: self.wait,
+            ping_interval: (!self.ping_interval.is_zero()).then_some(self.ping_interval),
+            idle_timeout: (!self.idle_timeout.is_zero()).then_some(self.idle_timeout),
+            close_code: self.close_code,
+            close_reason: self.close_reason,
+            replay_pace: if self.replay_step {
+                wire_cli::ReplayPace::Step
+            } else {
+                wire_cli::ReplayPace::Timed {
+                    speed: self.replay_speed,
+                }
+            },
+            #[cfg(feature = "tls")]
+            tls_ca_file: self.ca_file,
+            #[cfg(feature = "tls")]
+            tls_client_cert: self.client_cert,
+            #[cfg(feature = "tls")]
+            tls_client_key: self.client_key,
+            #[cfg(feature = "tls")]
+            tls_insecure: self.insecure,
+            ..Default::default()
+        };
+        if !self.no_history {
+            cfg.history_dir = self.history_dir.or_else(wire_cli::default_history_dir);
+        }
+
+        cfg
+    }
+}
+
+#[tokio::main]
+async fn main() -> color_eyre::Result<ExitCode> {
+    let cli = Cli::parse();
+    let headless = cli.headless || cli.script.is_some();
+    let script = cli.script.clone();
+    let cfg = cli.into_cfg();
+
+    // `ron::Value` drops struct and variant names, so RON requests are sent as written
+    // instead of being encoded again, which needs RON on both ends
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    if cfg.in_format == wire_cli::Format::Ron || cfg.out_format == wire_cli::Format::Ron {
+        if cfg.in_format != cfg.out_format {
+            color_eyre::eyre::bail!(
+                "--in ron and --out ron only work together, untyped values cannot carry \
+                 variant names between {} and {}",
+                cfg.in_format,
+                cfg.out_format
+            );
+        }
+        let cfg = wire_cli::ClientCfg {
+            verbatim_actions: true,
+            ..cfg
+        };
+        let client: wire_cli::Client<ron::Value, ron::Value, ron::Value> =
+            wire_cli::Client::new(cfg);
+        return run(client, headless, script).await;
+    }
+
+    let client: wire_cli::Client<serde_json::Value, serde_json::Value, serde_json::Value> =
+        wire_cli::Client::new(cfg);
+    run(client, headless, script).await
+}
+
+/// Runs the client with or without the terminal UI.
+async fn run<T>(
+    client: wire_cli::Client<T, T, T>,
+    headless: bool,
+    script: Option<std::path::PathBuf>,
+) -> color_eyre::Result<ExitCode>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    if !headless {
+        client.start().await?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match client.start_headless(script.as_deref()).await? {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Parses the name of a format enabled for the input box.
+fn parse_in_format(s: &str) -> Result<wire_cli::Format, String> {
+    let format: wire_cli::Format = s
+        .parse()
+        .map_err(|err: color_eyre::Report| err.to_string())?;
+    if !format.is_in() {
+        return Err(format!("{format} is only compiled in for the wire"));
+    }
+
+    Ok(format)
+}
+
+/// Parses the name of a format enabled for the wire.
+fn parse_out_format(s: &str) -> Result<wire_cli::Format, String> {
+    let format: wire_cli::Format = s
+        .parse()
+        .map_err(|err: color_eyre::Report| err.to_string())?;
+    if !format.is_out() {
+        return Err(format!("{format} is only compiled in for the input box"));
+    }
+
+    Ok(format)
+}
+
+/// Parses a non-negative number of seconds.
+fn parse_secs(s: &str) -> Result<Duration, String> {
+    let secs = s.parse::<f64>().map_err(|err| err.to_string())?;
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| format!("expected a non-negative number of seconds, got `{s}`"))
+}
+
+/// Parses a positive number of events per second.
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate = s.parse::<f64>().map_err(|err| err.to_string())?;
+    // the delay between events has to fit a `Duration` and must not round to zero
+    match Duration::try_from_secs_f64(1.0 / rate) {
+        Ok(delay) if !delay.is_zero() => Ok(rate),
+        _ => Err(format!("expected a positive number per second, got `{s}`")),
+    }
+}
+
+/// Parses a positive speed factor.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    let speed = s.parse::<f64>().map_err(|err| err.to_string())?;
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(format!("expected a positive, finite speed, got `{s}`"));
+    }
+
+    Ok(speed)
+}
+
+/// Parses a header given as `name: value`.
+fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected `name: value`, got `{s}`"))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|err| err.to_string())?;
+    let value = HeaderValue::from_str(value.trim()).map_err(|err| err.to_string())?;
+
+    Ok((name, value))
+}
+
+/// Parses a close code that may be sent in a close frame.
+fn parse_close_code(s: &str) -> Result<u16, String> {
+    let code = s.parse::<u16>().map_err(|err| err.to_string())?;
+    if !tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode::from(code).is_allowed()
+    {
+        return Err(format!("{code} may not be sent in a close frame"));
+    }
+
+    Ok(code)
+}
@@ -0,0 +1,102 @@
+use crate::conn::Outgoing;
+use crate::format::decode_hex;
+use crate::record::{MessageKind, MessageRecord, RawFrame};
+use base64::Engine;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The largest payload a ping may carry, as for every control frame.
+const MAX_PING_PAYLOAD: usize = 125;
+
+/// Decodes base64 with or without padding.
+const BASE64: base64::engine::GeneralPurpose = base64::engine::GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    base64::engine::GeneralPurposeConfig::new()
+        .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+);
+
+/// How input is sent when it bypasses the action format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RawMode {
+    /// A text frame with the input exactly as written.
+    Text,
+    /// A binary frame with the bytes of the hex digits, whitespace ignored.
+    Hex,
+    /// A binary frame with the bytes of the base64, whitespace ignored.
+    Base64,
+    /// A ping with the input as its payload.
+    Ping,
+}
+
+impl RawMode {
+    /// Returns the label shown in the input box and the Events list.
+    pub fn label(self) -> &'static str {
+        match self {
+            RawMode::Text => "text",
+            RawMode::Hex => "hex",
+            RawMode::Base64 => "base64",
+            RawMode::Ping => "ping",
+        }
+    }
+
+    /// Returns the mode after the given one, where `None` sends actions.
+    pub fn cycle(mode: Option<Self>) -> Option<Self> {
+        match mode {
+            None => Some(RawMode::Text),
+            Some(RawMode::Text) => Some(RawMode::Hex),
+            Some(RawMode::Hex) => Some(RawMode::Base64),
+            Some(RawMode::Base64) => Some(RawMode::Ping),
+            Some(RawMode::Ping) => None,
+        }
+    }
+
+    /// Builds the frame for the input, with a record marked as a raw send.
+    pub fn outgoing<Action>(self, input: &str) -> color_eyre::Result<Outgoing<Action>> {
+        let msg = match self {
+            RawMode::Text => Message::Text(input.into()),
+            RawMode::Hex => Message::Binary(decode_hex(input)?.into()),
+            RawMode::Base64 => {
+                let digits = input.split_whitespace().collect::<String>();
+                Message::Binary(BASE64.decode(digits)?.into())
+            }
+            RawMode::Ping => {
+                if input.len() > MAX_PING_PAYLOAD {
+                    color_eyre::eyre::bail!(
+                        "a ping carries at most {MAX_PING_PAYLOAD} bytes, got {}",
+                        input.len()
+                    );
+                }
+                Message::Ping(input.as_bytes().to_vec().into())
+            }
+        };
+
+        let raw = RawFrame::from_message(&msg);
+        let payload = match &raw {
+            Some(raw @ RawFrame::Binary(_)) => raw.to_string(),
+            _ => input.escape_debug().to_string(),
+        };
+        let mut record = MessageRecord::new(
+            MessageKind::Sent,
+            format!("raw {}: {payload}", self.label()),
+        )
+        .raw(raw);
+        record.raw_send = true;
+
+        Ok(Outgoing::Frame(msg, Box::new(record)))
+    }
+}
+
+impl std::str::FromStr for RawMode {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(RawMode::Text),
+            "hex" => Ok(RawMode::Hex),
+            "base64" => Ok(RawMode::Base64),
+            "ping" => Ok(RawMode::Ping),
+            _ => Err(color_eyre::eyre::eyre!(
+                "unknown raw mode `{s}`, expected text, hex, base64 or ping"
+            )),
+        }
+    }
+}
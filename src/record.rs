@@ -0,0 +1,323 @@
+use crate::tree::Tree;
+use crate::Res;
+use tokio_tungstenite::tungstenite::Message;
+
+/// What a message in the history is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MessageKind {
+    /// An action sent to the server.
+    Sent,
+    /// An event received from the server.
+    Received,
+    /// An error received from the server or a frame that could not be handled.
+    Error,
+    /// A message from the client itself.
+    System,
+}
+
+impl MessageKind {
+    /// Returns the label shown in the message list.
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageKind::Sent => "sent",
+            MessageKind::Received => "received",
+            MessageKind::Error => "error",
+            MessageKind::System => "system",
+        }
+    }
+}
+
+impl std::str::FromStr for MessageKind {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sent" => Ok(MessageKind::Sent),
+            "received" => Ok(MessageKind::Received),
+            "error" => Ok(MessageKind::Error),
+            "system" => Ok(MessageKind::System),
+            _ => Err(color_eyre::eyre::eyre!(
+                "unknown message kind `{s}`, expected sent, received, error or system"
+            )),
+        }
+    }
+}
+
+/// The payload of a data frame, exactly as it went over the socket.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RawFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl RawFrame {
+    /// Returns the payload of a data frame, or `None` for control frames.
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        match msg {
+            Message::Text(text) => Some(RawFrame::Text(text.to_string())),
+            Message::Binary(bytes) => Some(RawFrame::Binary(bytes.to_vec())),
+            _ => None,
+        }
+    }
+
+    /// Returns the frame carrying the payload.
+    pub fn to_message(&self) -> Message {
+        match self {
+            RawFrame::Text(text) => Message::Text(text.as_str().into()),
+            RawFrame::Binary(bytes) => Message::Binary(bytes.clone().into()),
+        }
+    }
+}
+
+impl std::fmt::Display for RawFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawFrame::Text(text) => f.write_str(text),
+            RawFrame::Binary(bytes) => {
+                for byte in bytes {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A message in the history.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct MessageRecord {
+    pub kind: MessageKind,
+    /// The one-line text shown in the message list.
+    pub text: String,
+    /// The raw frame, if the message went over the socket.
+    pub raw: Option<RawFrame>,
+    /// The decoded action, event or error, pretty-printed with `{:#?}`.
+    pub decoded: Option<String>,
+    /// The enum variant of the sent action or received event or error.
+    pub variant: Option<String>,
+    /// The timestamp of the received `wire::TimestampedEvent`.
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    /// The correlation id of the action or response, see [`crate::Correlate`].
+    pub correlation: Option<String>,
+    /// The time from sending the action to its first response, in milliseconds.
+    pub latency_ms: Option<f64>,
+    /// Whether the frame was sent as written in raw mode, bypassing the action format.
+    #[serde(default)]
+    pub raw_send: bool,
+    /// The index of the action in the message history this is a response to.
+    #[serde(skip)]
+    pub response_to: Option<usize>,
+    /// The local time the message was recorded at.
+    pub received_at: chrono::DateTime<chrono::Utc>,
+    /// The monotonic time the message was recorded at, which latencies are measured with.
+    #[serde(skip, default = "std::time::Instant::now")]
+    pub instant: std::time::Instant,
+}
+
+impl MessageRecord {
+    /// Creates a record without a frame.
+    pub fn new(kind: MessageKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            raw: None,
+            decoded: None,
+            variant: None,
+            timestamp: None,
+            correlation: None,
+            latency_ms: None,
+            raw_send: false,
+            response_to: None,
+            received_at: chrono::Utc::now(),
+            instant: std::time::Instant::now(),
+        }
+    }
+
+    /// Creates a record of a message from the client itself.
+    pub fn system(text: impl Into<String>) -> Self {
+        Self::new(MessageKind::System, text)
+    }
+
+    /// Creates a record of a decoded response.
+    pub fn received<Event, Err>(res: &Res<Event, Err>, raw: Option<RawFrame>) -> Self
+    where
+        Event: std::fmt::Debug + 'static,
+        Err: std::fmt::Debug + 'static,
+    {
+        let (record, path) = match res {
+            Ok(evt) => (
+                Self::new(MessageKind::Received, format!("{evt:?}"))
+                    .decoded(&evt.event)
+                    .timestamp(evt.timestamp),
+                ".event",
+            ),
+            Err(err) => (
+                Self::new(MessageKind::Error, format!("{err:?}")).decoded(err),
+                ".",
+            ),
+        };
+        let mut record = record.raw(raw);
+        if record.variant.is_none() {
+            record.variant = record.raw_variant(|tree| tree.variant_at(path));
+        }
+
+        record
+    }
+
+    /// Creates a record of an encoded action.
+    pub fn sent<Action>(action: &Action, raw: Option<RawFrame>) -> Self
+    where
+        Action: std::fmt::Debug + 'static,
+    {
+        let mut record = Self::new(MessageKind::Sent, format!("{action:?}"))
+            .decoded(action)
+            .raw(raw);
+        if record.variant.is_none() {
+            record.variant = record.raw_variant(Tree::variant);
+        }
+
+        record
+    }
+
+    /// Returns the variant found in the text frame.
+    ///
+    /// Values like `ron::Value` drop struct names, which the RON text still has.
+    fn raw_variant(&self, variant: impl FnOnce(&Tree) -> Option<&str>) -> Option<String> {
+        let Some(RawFrame::Text(text)) = &self.raw else {
+            return None;
+        };
+        variant(&Tree::parse(text)?).map(ToString::to_string)
+    }
+
+    /// Sets the raw frame.
+    pub fn raw(mut self, raw: Option<RawFrame>) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Sets the decoded value and its enum variant, see [`Tree::variant`].
+    pub fn decoded<T: std::fmt::Debug + 'static>(mut self, value: &T) -> Self {
+        let decoded = format!("{value:#?}");
+        self.variant = Tree::parse(&decoded).and_then(|tree| {
+            tree.untyped(is_untyped::<T>())
+                .variant()
+                .map(ToString::to_string)
+        });
+        self.decoded = Some(decoded);
+        self
+    }
+
+    /// Sets the correlation id.
+    pub fn correlation(mut self, id: Option<String>) -> Self {
+        self.correlation = id;
+        self
+    }
+
+    /// Sets the event timestamp.
+    pub fn timestamp(mut self, timestamp: chrono::DateTime<chrono::Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+}
+
+/// Returns whether the type is one of the untyped values the `wire-cli` binary decodes
+/// into, see [`Tree::untyped`].
+fn is_untyped<T: 'static>() -> bool {
+    let id = std::any::TypeId::of::<T>();
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    if id == std::any::TypeId::of::<ron::Value>() {
+        return true;
+    }
+
+    id == std::any::TypeId::of::<serde_json::Value>()
+}
+
+impl std::fmt::Display for MessageRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.received_at
+                .with_timezone(&chrono::Local)
+                .format("%H:%M:%S%.3f"),
+            self.kind.label(),
+            self.text
+        )?;
+        if let Some(latency) = self.latency_ms {
+            write!(f, " ({latency:.1} ms)")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_of_untyped_json_is_the_tag() {
+        let record = MessageRecord::new(MessageKind::Sent, "")
+            .decoded(&serde_json::json!({"Login": {"name": "bob"}}));
+        assert_eq!(record.variant.as_deref(), Some("Login"));
+
+        let record = MessageRecord::new(MessageKind::Sent, "").decoded(&serde_json::json!("Ping"));
+        assert_eq!(record.variant.as_deref(), Some("Ping"));
+
+        let record = MessageRecord::new(MessageKind::Sent, "")
+            .decoded(&serde_json::json!({"name": "bob", "room": "lobby"}));
+        assert_eq!(record.variant, None);
+    }
+
+    #[test]
+    fn typed_variants_may_share_untyped_names() {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        enum Event {
+            Map { size: u32 },
+            String(String),
+        }
+
+        let record = MessageRecord::new(MessageKind::Received, "").decoded(&Event::Map { size: 3 });
+        assert_eq!(record.variant.as_deref(), Some("Map"));
+        let record =
+            MessageRecord::new(MessageKind::Received, "").decoded(&Event::String("a".to_string()));
+        assert_eq!(record.variant.as_deref(), Some("String"));
+
+        // the same names are wrappers in untyped values
+        let record = MessageRecord::new(MessageKind::Received, "")
+            .decoded(&serde_json::json!({"Map": {"size": 3}}));
+        assert_eq!(record.variant.as_deref(), Some("Map"));
+        let record =
+            MessageRecord::new(MessageKind::Received, "").decoded(&serde_json::json!({"size": 3}));
+        assert_eq!(record.variant, None);
+    }
+
+    #[test]
+    fn variant_falls_back_to_the_ron_text() {
+        let action = serde_json::json!({"name": "bob"});
+        let record = MessageRecord::sent(
+            &action,
+            Some(RawFrame::Text(r#"Login(name: "bob")"#.to_string())),
+        );
+        assert_eq!(record.variant.as_deref(), Some("Login"));
+
+        let res: Res<serde_json::Value, serde_json::Value> = serde_json::from_str(
+            r#"{"Ok": {"timestamp": "2025-01-01T00:00:00Z", "event": {"name": "bob"}}}"#,
+        )
+        .unwrap();
+        let raw = RawFrame::Text(
+            r#"Ok((timestamp: "2025-01-01T00:00:00Z", event: Welcome(name: "bob")))"#.to_string(),
+        );
+        let record = MessageRecord::received(&res, Some(raw));
+        assert_eq!(record.variant.as_deref(), Some("Welcome"));
+
+        let res: Res<serde_json::Value, serde_json::Value> =
+            serde_json::from_str(r#"{"Err": "missing"}"#).unwrap();
+        let raw = RawFrame::Text(r#"Err(NotFound("missing"))"#.to_string());
+        let record = MessageRecord::received(&res, Some(raw));
+        assert_eq!(record.variant.as_deref(), Some("NotFound"));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::record::MessageRecord;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The version of the recording format, bumped on incompatible changes.
+pub(crate) const VERSION: u32 = 1;
+
+/// The first line of a recording.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Header {
+    pub version: u32,
+    /// The url of the server the session was recorded against.
+    pub url: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The notation the lines of a recording are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Notation {
+    /// One JSON object per line.
+    Json,
+    /// One RON value per line.
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    Ron,
+}
+
+impl Notation {
+    /// Picks the notation from the extension of the file, defaulting to JSON.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Some("ron" | "ronl") => Notation::Ron,
+            _ => Notation::Json,
+        }
+    }
+
+    /// Writes a value on a single line.
+    pub fn format_line(self, value: &impl serde::Serialize) -> std::io::Result<String> {
+        match self {
+            Notation::Json => Ok(serde_json::to_string(value)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Notation::Ron => ron::to_string(value).map_err(std::io::Error::other),
+        }
+    }
+
+    /// Reads a value from a line.
+    pub fn parse_line<T: serde::de::DeserializeOwned>(self, line: &str) -> color_eyre::Result<T> {
+        match self {
+            Notation::Json => Ok(serde_json::from_str(line)?),
+            #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+            Notation::Ron => Ok(ron::from_str(line)?),
+        }
+    }
+}
+
+/// Records a session, every message on its own line after a [`Header`].
+///
+/// See `RECORDING.md` for the format.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    path: PathBuf,
+    notation: Notation,
+    file: std::io::LineWriter<std::fs::File>,
+}
+
+impl Recorder {
+    /// Creates the recording, overwriting the file if it exists.
+    pub fn create(path: &Path, url: &str) -> std::io::Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Self::start(path, file, url)
+    }
+
+    /// Creates the recording in a new file named after the current time, never
+    /// overwriting an existing one.
+    pub fn create_new(url: &str) -> std::io::Result<Self> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        for n in 0.. {
+            let path = match n {
+                0 => PathBuf::from(format!("wire-cli-{stamp}.ndjson")),
+                n => PathBuf::from(format!("wire-cli-{stamp}-{n}.ndjson")),
+            };
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => return Self::start(&path, file, url),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!("ran out of recording file names")
+    }
+
+    /// Writes the header to a freshly opened file.
+    fn start(path: &Path, file: std::fs::File, url: &str) -> std::io::Result<Self> {
+        let mut recorder = Self {
+            path: path.to_path_buf(),
+            notation: Notation::of(path),
+            file: std::io::LineWriter::new(file),
+        };
+
+        let header = Header {
+            version: VERSION,
+            url: url.to_string(),
+            started_at: chrono::Utc::now(),
+        };
+        let line = recorder.notation.format_line(&header)?;
+        writeln!(recorder.file, "{line}")?;
+
+        Ok(recorder)
+    }
+
+    /// Appends a message.
+    pub fn write(&mut self, msg: &MessageRecord) -> std::io::Result<()> {
+        let line = self.notation.format_line(msg)?;
+        writeln!(self.file, "{line}")
+    }
+
+    /// Returns the path of the recording.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
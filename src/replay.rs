@@ -0,0 +1,309 @@
+use crate::record::{MessageKind, MessageRecord};
+use crate::recording::{Header, Notation, VERSION};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How recorded actions are paced when replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayPace {
+    /// With the recorded delays between actions, divided by the speed.
+    Timed { speed: f64 },
+    /// One action per key press.
+    Step,
+}
+
+impl Default for ReplayPace {
+    fn default() -> Self {
+        ReplayPace::Timed { speed: 1.0 }
+    }
+}
+
+/// A recorded session being replayed, comparing the received events to the recorded ones.
+#[derive(Debug)]
+pub(crate) struct Replay {
+    /// The recorded actions, with their delays from the first one.
+    actions: Vec<(Duration, MessageRecord)>,
+    sent: usize,
+    pace: ReplayPace,
+    started: Option<Instant>,
+    /// The recorded events not received yet.
+    expected: VecDeque<MessageRecord>,
+    matched: usize,
+    differed: usize,
+    /// The events received after all recorded ones were.
+    extra: usize,
+}
+
+impl Replay {
+    /// Loads a recording.
+    ///
+    /// Only events recorded after the first action are expected, as earlier ones depend on
+    /// when the recording was started.
+    pub fn load(path: &Path, pace: ReplayPace) -> color_eyre::Result<Self> {
+        let notation = Notation::of(path);
+        let text = std::fs::read_to_string(path)?;
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+
+        let header: Header = notation.parse_line(
+            lines
+                .next()
+                .ok_or_else(|| color_eyre::eyre::eyre!("empty recording"))?,
+        )?;
+        if header.version > VERSION {
+            color_eyre::eyre::bail!(
+                "recording has version {}, only up to {VERSION} is supported",
+                header.version
+            );
+        }
+
+        let mut actions = Vec::new();
+        let mut expected = VecDeque::new();
+        let mut first = None;
+        for line in lines {
+            let msg: MessageRecord = notation.parse_line(line)?;
+            match msg.kind {
+                MessageKind::Sent if msg.raw.is_some() => {
+                    let first = *first.get_or_insert(msg.received_at);
+                    let delay = (msg.received_at - first).to_std().unwrap_or_default();
+                    actions.push((delay, msg));
+                }
+                MessageKind::Received | MessageKind::Error if first.is_some() => {
+                    expected.push_back(msg)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            actions,
+            sent: 0,
+            pace,
+            started: None,
+            expected,
+            matched: 0,
+            differed: 0,
+            extra: 0,
+        })
+    }
+
+    /// Takes the actions due for sending, starting the clock on the first call.
+    pub fn due(&mut self, now: Instant) -> Vec<MessageRecord> {
+        let ReplayPace::Timed { speed } = self.pace else {
+            return Vec::new();
+        };
+        let started = *self.started.get_or_insert(now);
+        // a huge speed sends everything at once instead of overflowing
+        let elapsed = Duration::try_from_secs_f64(
+            now.duration_since(started).as_secs_f64() * speed.max(f64::EPSILON),
+        )
+        .unwrap_or(Duration::MAX);
+
+        let mut due = Vec::new();
+        while let Some((delay, msg)) = self.actions.get(self.sent) {
+            if *delay > elapsed {
+                break;
+            }
+            due.push(msg.clone());
+            self.sent += 1;
+        }
+
+        due
+    }
+
+    /// Takes the next action regardless of the pace.
+    pub fn step(&mut self) -> Option<MessageRecord> {
+        let (_, msg) = self.actions.get(self.sent)?;
+        self.sent += 1;
+        Some(msg.clone())
+    }
+
+    /// Returns whether all actions were sent.
+    pub fn is_done(&self) -> bool {
+        self.sent == self.actions.len()
+    }
+
+    /// Compares a received message to the next recorded event, returning a report if they
+    /// differ.
+    ///
+    /// Events received before the first action is sent are not compared, like they are
+    /// not expected. Past the recorded events, only the first extra one is reported and
+    /// the others are counted in the status.
+    pub fn compare(&mut self, msg: &MessageRecord) -> Option<MessageRecord> {
+        if self.sent == 0 || !matches!(msg.kind, MessageKind::Received | MessageKind::Error) {
+            return None;
+        }
+
+        let Some(expected) = self.expected.pop_front() else {
+            self.extra += 1;
+            return (self.extra == 1).then(|| {
+                MessageRecord::new(
+                    MessageKind::Error,
+                    format!(
+                        "replay: received more events than recorded, starting with: {}",
+                        msg.text
+                    ),
+                )
+            });
+        };
+        if expected.kind == msg.kind && expected.decoded == msg.decoded {
+            self.matched += 1;
+            return None;
+        }
+
+        self.differed += 1;
+        let old = expected.decoded.as_deref().unwrap_or(&expected.text);
+        let new = msg.decoded.as_deref().unwrap_or(&msg.text);
+        let mut report = MessageRecord::new(
+            MessageKind::Error,
+            format!(
+                "replay: expected {} {}",
+                expected.kind.label(),
+                expected.text
+            ),
+        );
+        report.decoded = Some(diff(old, new));
+        Some(report)
+    }
+
+    /// Returns a short summary of the progress.
+    pub fn status(&self) -> String {
+        let pace = match self.pace {
+            ReplayPace::Timed { speed } => format!("{speed}x"),
+            ReplayPace::Step => "step".to_string(),
+        };
+        format!(
+            "replay {pace}: sent {}/{}, {} matched, {} differed, {} pending, {} extra",
+            self.sent,
+            self.actions.len(),
+            self.matched,
+            self.differed,
+            self.expected.len(),
+            self.extra
+        )
+    }
+}
+
+/// The most cells of the LCS table [`diff`] allocates before giving up on aligning lines.
+const MAX_DIFF_CELLS: usize = 1 << 20;
+
+/// Diffs two texts line by line, prefixing removed lines with `-` and added ones with `+`.
+///
+/// If the differing lines are too many to align, all of them are shown as removed and
+/// then added.
+fn diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // the common ends are kept as is, only the lines in between are aligned
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (head, tail) = (&old[..prefix], &old[old.len() - suffix..]);
+    let (old, new) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut out = head
+        .iter()
+        .map(|line| format!("  {line}"))
+        .collect::<Vec<_>>();
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_DIFF_CELLS {
+        out.extend(old.iter().map(|line| format!("- {line}")));
+        out.extend(new.iter().map(|line| format!("+ {line}")));
+    } else {
+        // the lengths of the longest common subsequences of the suffixes
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                out.push(format!("  {}", old[i]));
+                i += 1;
+                j += 1;
+            } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                out.push(format!("- {}", old[i]));
+                i += 1;
+            } else {
+                out.push(format!("+ {}", new[j]));
+                j += 1;
+            }
+        }
+    }
+    out.extend(tail.iter().map(|line| format!("  {line}")));
+
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(text: &str) -> MessageRecord {
+        let mut msg = MessageRecord::new(MessageKind::Received, text);
+        msg.decoded = Some(text.to_string());
+        msg
+    }
+
+    #[test]
+    fn compares_only_after_the_first_action() {
+        let mut replay = Replay {
+            actions: vec![(
+                Duration::ZERO,
+                MessageRecord::new(MessageKind::Sent, "Ping"),
+            )],
+            sent: 0,
+            pace: ReplayPace::Step,
+            started: None,
+            expected: [event("Pong")].into(),
+            matched: 0,
+            differed: 0,
+            extra: 0,
+        };
+
+        // a greeting sent on connect is not part of the replayed session
+        assert!(replay.compare(&event("Welcome")).is_none());
+        assert_eq!(replay.expected.len(), 1);
+
+        assert!(replay.step().is_some());
+        assert!(replay.compare(&event("Pong")).is_none());
+        assert_eq!((replay.matched, replay.differed), (1, 0));
+
+        assert!(replay.compare(&event("Pong")).is_some());
+        assert!(replay.compare(&event("Pong")).is_none());
+        assert!(replay.status().ends_with("0 pending, 2 extra"));
+    }
+
+    #[test]
+    fn diff_aligns_the_changed_lines() {
+        assert_eq!(diff("a\nb\nc\nd", "a\nx\nc\nd"), "  a\n- b\n+ x\n  c\n  d");
+        assert_eq!(diff("a\nb", "a\nb\nc"), "  a\n  b\n+ c");
+    }
+
+    #[test]
+    fn diff_of_large_texts_is_not_aligned() {
+        let old = (0..2000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let new = (0..2000).map(|i| (i * 2).to_string()).collect::<Vec<_>>();
+        let out = diff(&old.join("\n"), &new.join("\n"));
+
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 1999 * 2);
+        assert_eq!(lines[0], "  0");
+        assert_eq!(lines[1], "- 1");
+        assert_eq!(lines[2000], "+ 2");
+    }
+}
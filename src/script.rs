@@ -0,0 +1,394 @@
+use crate::conn::Outgoing;
+use crate::raw::RawMode;
+use crate::record::{MessageKind, MessageRecord};
+use crate::tree::Tree;
+use crate::Format;
+use std::time::Duration;
+
+/// How long an `expect` step waits unless the script says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A step of a headless script.
+#[derive(Debug)]
+pub(crate) enum Step<Action> {
+    /// Sends an action or a raw frame.
+    Send(Outgoing<Action>),
+    /// Waits for an event or error matching the pattern.
+    Expect { pattern: Pattern, timeout: Duration },
+}
+
+/// Parses a headless script line by line.
+///
+/// See `SCRIPTS.md` for the format.
+#[derive(Debug)]
+pub(crate) struct Script {
+    format: Format,
+    /// Whether actions are sent as written, see [`Outgoing::parse`].
+    verbatim: bool,
+    /// The timeout of `expect` steps without a `within`.
+    timeout: Duration,
+}
+
+impl Script {
+    /// Creates a parser for actions in the format.
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            verbatim: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sends actions as written instead of encoding them again.
+    pub fn verbatim(mut self, verbatim: bool) -> Self {
+        self.verbatim = verbatim;
+        self
+    }
+
+    /// Parses a line, returning `None` for empty lines, comments and directives.
+    pub fn parse_line<Action>(&mut self, line: &str) -> color_eyre::Result<Option<Step<Action>>>
+    where
+        Action: serde::de::DeserializeOwned + std::fmt::Debug + 'static,
+    {
+        // trailing whitespace may be part of a raw payload
+        let untrimmed = line.trim_start();
+        let line = untrimmed.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match word {
+            "send" => Ok(Some(Step::Send(Outgoing::parse(
+                self.format,
+                self.verbatim,
+                rest.trim(),
+            )?))),
+            "raw" => {
+                // the payload is everything after the single space following the mode
+                let rest = untrimmed
+                    .split_once(char::is_whitespace)
+                    .map_or("", |(_, rest)| rest);
+                let (mode, payload) = rest.split_once(' ').unwrap_or((rest, ""));
+                Ok(Some(Step::Send(
+                    mode.parse::<RawMode>()?.outgoing(payload)?,
+                )))
+            }
+            "expect" => {
+                // `within` only counts as a whole term, not inside a quoted value
+                let mut terms = terms(rest)?;
+                let timeout = match terms.as_slice() {
+                    [.., within, timeout] if within == "within" => {
+                        let timeout = parse_duration(timeout)?;
+                        terms.truncate(terms.len() - 2);
+                        timeout
+                    }
+                    _ => self.timeout,
+                };
+                Ok(Some(Step::Expect {
+                    pattern: terms.join(" ").parse()?,
+                    timeout,
+                }))
+            }
+            "timeout" => {
+                self.timeout = parse_duration(rest)?;
+                Ok(None)
+            }
+            // a bare action, as in plain headless input
+            _ => Ok(Some(Step::Send(Outgoing::parse(
+                self.format,
+                self.verbatim,
+                line,
+            )?))),
+        }
+    }
+}
+
+/// What an `expect` step matches.
+///
+/// Made of whitespace separated terms, all of which have to match:
+/// - `err` matches the `Err` branch of the response instead of an event,
+/// - a name starting uppercase matches the enum variant,
+/// - `.path=value`, `.path!=value` and `.path~regex` match the field at the jq-like path.
+#[derive(Debug)]
+pub(crate) struct Pattern {
+    expr: String,
+    err: bool,
+    variant: Option<String>,
+    fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+struct Field {
+    path: String,
+    op: Op,
+}
+
+#[derive(Debug)]
+enum Op {
+    Eq(String),
+    Ne(String),
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// Returns whether the message is a response matching the pattern.
+    pub fn matches(&self, msg: &MessageRecord) -> bool {
+        let kind = if self.err {
+            MessageKind::Error
+        } else {
+            MessageKind::Received
+        };
+        // errors without a decoded value are frames that could not be handled
+        let Some(decoded) = msg.decoded.as_deref().filter(|_| msg.kind == kind) else {
+            return false;
+        };
+        // the same variant the Events filter and recordings see
+        if self.variant.is_some() && msg.variant != self.variant {
+            return false;
+        }
+        if self.fields.is_empty() {
+            return true;
+        }
+        let Some(tree) = Tree::parse(decoded) else {
+            return false;
+        };
+
+        self.fields.iter().all(|field| {
+            let Some(value) = tree.get(&field.path) else {
+                return false;
+            };
+            let value = unquote(value);
+            match &field.op {
+                Op::Eq(expected) => value == expected,
+                Op::Ne(expected) => value != expected,
+                Op::Regex(regex) => regex.is_match(value),
+            }
+        })
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pattern = Self {
+            expr: s.trim().to_string(),
+            err: false,
+            variant: None,
+            fields: Vec::new(),
+        };
+
+        for term in terms(s)? {
+            if term == "err" {
+                pattern.err = true;
+            } else if term.starts_with('.') || term.starts_with('[') {
+                pattern.fields.push(term.parse()?);
+            } else if term.starts_with(char::is_uppercase) {
+                pattern.variant = Some(term);
+            } else {
+                color_eyre::eyre::bail!(
+                    "unknown term `{term}`, expected `err`, a variant or a `.path=value` field"
+                );
+            }
+        }
+
+        Ok(pattern)
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl std::str::FromStr for Field {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let idx = s
+            .find(['=', '~', '!'])
+            .ok_or_else(|| color_eyre::eyre::eyre!("field `{s}` needs `=`, `!=` or `~`"))?;
+        let (path, op) = s.split_at(idx);
+        let op = if let Some(value) = op.strip_prefix("!=") {
+            Op::Ne(unquote(value).to_string())
+        } else if let Some(value) = op.strip_prefix('=') {
+            Op::Eq(unquote(value).to_string())
+        } else if let Some(value) = op.strip_prefix('~') {
+            Op::Regex(regex::Regex::new(unquote(value))?)
+        } else {
+            color_eyre::eyre::bail!("field `{s}` needs `=`, `!=` or `~`");
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            op,
+        })
+    }
+}
+
+/// Splits a pattern at whitespace outside of double quotes.
+fn terms(s: &str) -> color_eyre::Result<Vec<String>> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for ch in s.chars() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        term.push(ch);
+    }
+    if quoted {
+        color_eyre::eyre::bail!("unterminated string in `{s}`");
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+
+    Ok(terms)
+}
+
+/// Strips the double quotes around a string.
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+/// Parses a duration like `500ms`, `2s` or `1.5`, in seconds without a unit.
+fn parse_duration(s: &str) -> color_eyre::Result<Duration> {
+    let s = s.trim();
+    let (number, scale) = match s.strip_suffix("ms") {
+        Some(number) => (number, 0.001),
+        None => (s.strip_suffix('s').unwrap_or(s), 1.0),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs * scale).ok())
+        .ok_or_else(|| color_eyre::eyre::eyre!("invalid duration `{s}`"))
+}
+
+#[cfg(all(test, feature = "in-json"))]
+mod tests {
+    use super::*;
+
+    /// Decodes a response the way the `wire-cli` binary does.
+    fn received(json: &str) -> MessageRecord {
+        let res: crate::Res<serde_json::Value, serde_json::Value> =
+            serde_json::from_str(json).unwrap();
+        MessageRecord::received(&res, None)
+    }
+
+    fn expect(line: &str) -> Pattern {
+        match Script::new(Format::Json).parse_line::<serde_json::Value>(line) {
+            Ok(Some(Step::Expect { pattern, .. })) => pattern,
+            other => panic!("`{line}` is not an expect step: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn raw_payloads_keep_their_whitespace() {
+        let step = Script::new(Format::Json).parse_line::<serde_json::Value>("  raw text  a b \t");
+        let Ok(Some(Step::Send(Outgoing::Frame(msg, _)))) = step else {
+            panic!("not a raw step: {step:?}");
+        };
+        assert_eq!(msg.into_text().unwrap().as_str(), " a b \t");
+    }
+
+    #[test]
+    fn documented_example_matches_untyped_values() {
+        let welcome = received(
+            r#"{"Ok": {"timestamp": "2025-01-01T00:00:00Z", "event": {"Welcome": {"name": "bob"}}}}"#,
+        );
+        assert!(expect("expect Welcome .name=bob").matches(&welcome));
+        assert!(expect("expect .Welcome.name=bob").matches(&welcome));
+        assert!(expect("expect Welcome").matches(&welcome));
+        assert!(!expect("expect Welcome .name=alice").matches(&welcome));
+        assert!(!expect("expect Echo").matches(&welcome));
+        assert!(!expect("expect err").matches(&welcome));
+
+        let not_found = received(r#"{"Err": {"NotFound": {"room": "missing"}}}"#);
+        assert!(expect("expect err NotFound within 500ms").matches(&not_found));
+        assert!(expect("expect err .room=missing").matches(&not_found));
+        assert!(expect("expect err").matches(&not_found));
+        assert!(!expect("expect NotFound").matches(&not_found));
+    }
+
+    #[test]
+    fn within_is_only_a_keyword_outside_quotes() {
+        let mut script = Script::new(Format::Json);
+        let step = script.parse_line::<serde_json::Value>(r#"expect .msg="ok within 2s""#);
+        let Ok(Some(Step::Expect { pattern, timeout })) = step else {
+            panic!("not an expect step: {step:?}");
+        };
+        assert_eq!(pattern.to_string(), r#".msg="ok within 2s""#);
+        assert_eq!(timeout, DEFAULT_TIMEOUT);
+
+        let step = script.parse_line::<serde_json::Value>(r#"expect .msg="a b" within 250ms"#);
+        let Ok(Some(Step::Expect { pattern, timeout })) = step else {
+            panic!("not an expect step: {step:?}");
+        };
+        assert_eq!(pattern.to_string(), r#".msg="a b""#);
+        assert_eq!(timeout, Duration::from_millis(250));
+
+        let step = script.parse_line::<serde_json::Value>("expect within 1s");
+        assert!(matches!(
+            step,
+            Ok(Some(Step::Expect { timeout, .. })) if timeout == Duration::from_secs(1)
+        ));
+
+        let msg = received(
+            r#"{"Ok": {"timestamp": "2025-01-01T00:00:00Z", "event": {"Said": {"msg": "ok within 2s"}}}}"#,
+        );
+        assert!(expect(r#"expect Said .msg="ok within 2s""#).matches(&msg));
+    }
+
+    #[test]
+    fn huge_durations_are_rejected() {
+        let mut script = Script::new(Format::Json);
+        for line in ["timeout 1e30", "expect within 1e300s", "timeout -1s"] {
+            assert!(
+                script.parse_line::<serde_json::Value>(line).is_err(),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn unit_variants_match_as_strings() {
+        let pong = received(r#"{"Ok": {"timestamp": "2025-01-01T00:00:00Z", "event": "Pong"}}"#);
+        assert!(expect("expect Pong").matches(&pong));
+        assert!(!expect("expect Ping").matches(&pong));
+    }
+
+    #[test]
+    fn typed_variants_match_by_name() {
+        // only read through `Debug`
+        #[allow(dead_code)]
+        #[derive(Debug, serde::Deserialize)]
+        enum Event {
+            Welcome { name: String },
+        }
+
+        let res: crate::Res<Event, String> = serde_json::from_str(
+            r#"{"Ok": {"timestamp": "2025-01-01T00:00:00Z", "event": {"Welcome": {"name": "bob"}}}}"#,
+        )
+        .unwrap();
+        let welcome = MessageRecord::received(&res, None);
+        assert!(expect("expect Welcome .name=bob").matches(&welcome));
+        assert!(!expect("expect Welcome .name=alice").matches(&welcome));
+    }
+}
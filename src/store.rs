@@ -0,0 +1,465 @@
+use crate::record::MessageRecord;
+use std::collections::VecDeque;
+use std::io::{BufRead, Seek, Write};
+
+/// The messages shown in the Events list.
+///
+/// Up to `memory` messages are kept in memory. Past that, every message is also appended
+/// to a temporary spill file as a JSON line, and memory only holds a window of them which
+/// is paged back and forth when scrolling past its edges.
+///
+/// Messages are indexed by the order they were added in, starting at 0.
+#[derive(Debug)]
+pub(crate) struct Store {
+    window: VecDeque<MessageRecord>,
+    /// The index of the first message in the window.
+    start: usize,
+    /// The number of messages ever added.
+    total: usize,
+    /// The maximum number of messages kept, unbounded if unset.
+    limit: Option<usize>,
+    /// The maximum number of messages kept in memory.
+    memory: usize,
+    spill: Option<Spill>,
+    /// Set once spilling failed, old messages are dropped from then on.
+    spill_failed: bool,
+}
+
+impl Store {
+    /// Creates an empty store.
+    pub fn new(limit: Option<usize>, memory: usize) -> Self {
+        Self {
+            window: VecDeque::new(),
+            start: 0,
+            total: 0,
+            limit,
+            memory: memory.max(1),
+            spill: None,
+            spill_failed: false,
+        }
+    }
+
+    /// Adds a message, moving the oldest ones out of memory if needed.
+    ///
+    /// If the window is not at the newest messages, the message is only added to the
+    /// spill file.
+    pub fn push(&mut self, msg: MessageRecord) -> std::io::Result<()> {
+        let following = self.is_at_end();
+        self.total += 1;
+
+        let mut result = Ok(());
+        let first = self.first();
+        if let Some(spill) = &mut self.spill {
+            if let Err(err) = spill.append(&msg) {
+                self.spill = None;
+                self.spill_failed = true;
+                result = Err(err);
+            } else if self
+                .limit
+                .is_some_and(|limit| first - spill.base >= limit.max(self.memory))
+            {
+                // messages past the limit can never be paged in again
+                result = spill.compact(first);
+            }
+        }
+        if !following {
+            if self.spill.is_some() {
+                return result;
+            }
+
+            // without the spill file the window cannot be paged to the newest messages
+            self.window.clear();
+            self.start = self.total - 1;
+        }
+        self.window.push_back(msg);
+
+        let max = self
+            .limit
+            .map_or(self.memory, |limit| limit.min(self.memory));
+        if self.window.len() > max
+            && self.spill.is_none()
+            && !self.spill_failed
+            && self.limit.is_none_or(|limit| limit > self.memory)
+        {
+            match Spill::create(self.start, &self.window) {
+                Ok(spill) => self.spill = Some(spill),
+                Err(err) => {
+                    self.spill_failed = true;
+                    result = Err(err);
+                }
+            }
+        }
+        while self.window.len() > max {
+            self.window.pop_front();
+            self.start += 1;
+        }
+
+        result
+    }
+
+    /// Moves the window to older messages, returning how many were paged in.
+    pub fn page_up(&mut self) -> std::io::Result<usize> {
+        let first = self.first();
+        let Some(spill) = &mut self.spill else {
+            return Ok(0);
+        };
+        if self.start <= first {
+            return Ok(0);
+        }
+
+        let from = self
+            .start
+            .saturating_sub(self.memory.div_ceil(2))
+            .max(first);
+        let msgs = spill.read(from..self.start)?;
+        let len = msgs.len();
+        for msg in msgs.into_iter().rev() {
+            self.window.push_front(msg);
+        }
+        self.start -= len;
+        self.window.truncate(self.memory);
+
+        Ok(len)
+    }
+
+    /// Moves the window to newer messages, returning how many were paged in.
+    pub fn page_down(&mut self) -> std::io::Result<usize> {
+        let end = self.start + self.window.len();
+        let first = self.first();
+        let Some(spill) = &mut self.spill else {
+            return Ok(0);
+        };
+        if end >= self.total {
+            return Ok(0);
+        }
+        if end < first {
+            // the window fell past the limit while paged away
+            self.page_end()?;
+            return Ok(self.window.len());
+        }
+
+        let to = (end + self.memory.div_ceil(2)).min(self.total);
+        let msgs = spill.read(end..to)?;
+        let len = msgs.len();
+        self.window.extend(msgs);
+        while self.window.len() > self.memory {
+            self.window.pop_front();
+            self.start += 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Moves the window to the newest messages.
+    pub fn page_end(&mut self) -> std::io::Result<()> {
+        let from = self.total.saturating_sub(self.memory).max(self.first());
+        if self.is_at_end() {
+            return Ok(());
+        }
+        let Some(spill) = &mut self.spill else {
+            return Ok(());
+        };
+
+        self.window = spill.read(from..self.total)?.into();
+        self.start = from;
+
+        Ok(())
+    }
+
+    /// Returns whether the window holds the newest message.
+    pub fn is_at_end(&self) -> bool {
+        self.start + self.window.len() == self.total
+    }
+
+    /// Returns the index of the oldest message that can still be paged in.
+    pub fn first(&self) -> usize {
+        let kept = self
+            .limit
+            .map_or(0, |limit| self.total.saturating_sub(limit));
+        let stored = self.spill.as_ref().map_or(self.start, |spill| spill.base);
+
+        kept.max(stored)
+    }
+
+    /// Returns the index of the first message in the window.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the number of messages in the window.
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Returns the number of messages ever added.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Iterates over the messages in the window with their indices.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &MessageRecord)> {
+        (self.start..).zip(&self.window)
+    }
+}
+
+impl std::ops::Index<usize> for Store {
+    type Output = MessageRecord;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.window[idx - self.start]
+    }
+}
+
+/// An append-only file of messages moved out of memory.
+#[derive(Debug)]
+struct Spill {
+    path: std::path::PathBuf,
+    writer: std::io::BufWriter<std::fs::File>,
+    reader: std::io::BufReader<std::fs::File>,
+    /// The index of the first message in the file.
+    base: usize,
+    /// The byte offset of every message in the file.
+    offsets: Vec<u64>,
+    len: u64,
+}
+
+impl Spill {
+    /// Creates a spill file in the temporary directory, starting with the messages.
+    fn create<'a>(
+        base: usize,
+        msgs: impl IntoIterator<Item = &'a MessageRecord>,
+    ) -> std::io::Result<Self> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("wire-cli-{}-{nanos}.ndjson", std::process::id()));
+        let writer = std::fs::OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)?;
+        let reader = std::fs::File::open(&path)?;
+
+        let mut spill = Self {
+            path,
+            writer: std::io::BufWriter::new(writer),
+            reader: std::io::BufReader::new(reader),
+            base,
+            offsets: Vec::new(),
+            len: 0,
+        };
+        for msg in msgs {
+            spill.append(msg)?;
+        }
+
+        Ok(spill)
+    }
+
+    fn append(&mut self, msg: &MessageRecord) -> std::io::Result<()> {
+        // the link to the request is not part of the recorded format
+        let line = serde_json::to_string(&(msg.response_to, msg))?;
+        writeln!(self.writer, "{line}")?;
+        self.offsets.push(self.len);
+        self.len += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    /// Reads back a range of messages.
+    fn read(&mut self, range: std::ops::Range<usize>) -> std::io::Result<Vec<MessageRecord>> {
+        let Some(&offset) = range
+            .start
+            .checked_sub(self.base)
+            .and_then(|idx| self.offsets.get(idx))
+        else {
+            return Ok(Vec::new());
+        };
+        self.writer.flush()?;
+        self.reader.seek(std::io::SeekFrom::Start(offset))?;
+
+        let mut line = String::new();
+        range
+            .map(|_| {
+                line.clear();
+                self.reader.read_line(&mut line)?;
+                let (response_to, mut msg): (_, MessageRecord) = serde_json::from_str(&line)?;
+                msg.response_to = response_to;
+                Ok(msg)
+            })
+            .collect()
+    }
+
+    /// Drops the messages before `from`, moving the rest to a new file.
+    fn compact(&mut self, from: usize) -> std::io::Result<()> {
+        let Some(&offset) = self.offsets.get(from - self.base) else {
+            return Ok(());
+        };
+        self.writer.flush()?;
+        self.reader.seek(std::io::SeekFrom::Start(offset))?;
+
+        let mut spill = Self::create(from, [])?;
+        std::io::copy(&mut self.reader, &mut spill.writer)?;
+        spill.offsets = self.offsets[from - self.base..]
+            .iter()
+            .map(|pos| pos - offset)
+            .collect();
+        spill.len = self.len - offset;
+
+        // the old file is removed when dropped
+        *self = spill;
+        Ok(())
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::MessageKind;
+
+    fn filled(limit: Option<usize>, memory: usize, count: usize) -> Store {
+        let mut store = Store::new(limit, memory);
+        for i in 0..count {
+            store
+                .push(MessageRecord::new(MessageKind::Sent, i.to_string()))
+                .unwrap();
+        }
+        store
+    }
+
+    /// Returns the indices in the window, checking they match the message texts.
+    fn window(store: &Store) -> Vec<usize> {
+        store
+            .iter()
+            .map(|(idx, msg)| {
+                assert_eq!(msg.text, idx.to_string());
+                idx
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pages_across_the_spill_boundary() {
+        let mut store = filled(None, 4, 10);
+        assert_eq!(store.first(), 0);
+        assert_eq!(window(&store), [6, 7, 8, 9]);
+
+        assert_eq!(store.page_up().unwrap(), 2);
+        assert_eq!(window(&store), [4, 5, 6, 7]);
+        assert!(!store.is_at_end());
+        assert_eq!(store.page_up().unwrap(), 2);
+        assert_eq!(store.page_up().unwrap(), 2);
+        assert_eq!(window(&store), [0, 1, 2, 3]);
+        assert_eq!(store.page_up().unwrap(), 0);
+
+        assert_eq!(store.page_down().unwrap(), 2);
+        assert_eq!(window(&store), [2, 3, 4, 5]);
+        store.page_end().unwrap();
+        assert_eq!(window(&store), [6, 7, 8, 9]);
+        assert!(store.is_at_end());
+        assert_eq!(store.page_down().unwrap(), 0);
+    }
+
+    #[test]
+    fn limit_below_memory_drops_without_spilling() {
+        let mut store = filled(Some(3), 4, 10);
+        assert!(store.spill.is_none());
+        assert_eq!(store.first(), 7);
+        assert_eq!(window(&store), [7, 8, 9]);
+        assert_eq!(store.page_up().unwrap(), 0);
+    }
+
+    #[test]
+    fn limit_above_memory_bounds_paging() {
+        let mut store = filled(Some(6), 4, 10);
+        assert_eq!(store.total(), 10);
+        assert_eq!(store.first(), 4);
+        assert_eq!(window(&store), [6, 7, 8, 9]);
+
+        assert_eq!(store.page_up().unwrap(), 2);
+        assert_eq!(window(&store), [4, 5, 6, 7]);
+        assert_eq!(store.page_up().unwrap(), 0);
+
+        // messages past the limit cannot be paged in once newer ones arrive
+        store
+            .push(MessageRecord::new(MessageKind::Sent, "10"))
+            .unwrap();
+        assert_eq!(store.first(), 5);
+        store.page_end().unwrap();
+        assert_eq!(window(&store), [7, 8, 9, 10]);
+        assert_eq!(store.page_up().unwrap(), 2);
+        assert_eq!(window(&store), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn spill_is_compacted_to_the_limit() {
+        let mut store = filled(Some(6), 4, 100);
+        let spill = store.spill.as_ref().unwrap();
+        assert!(spill.offsets.len() <= 12);
+        assert!(spill.base > 0);
+        assert_eq!(spill.base + spill.offsets.len(), 100);
+
+        assert_eq!(store.first(), 94);
+        store.page_up().unwrap();
+        assert_eq!(window(&store), [94, 95, 96, 97]);
+        store.page_end().unwrap();
+        assert_eq!(window(&store), [96, 97, 98, 99]);
+    }
+
+    #[test]
+    fn page_down_after_compacting_past_the_window() {
+        let mut store = filled(Some(6), 4, 10);
+        store.page_up().unwrap();
+        assert_eq!(window(&store), [4, 5, 6, 7]);
+        for i in 10..30 {
+            store
+                .push(MessageRecord::new(MessageKind::Sent, i.to_string()))
+                .unwrap();
+        }
+        assert!(store.start() < store.first());
+
+        assert_eq!(store.page_down().unwrap(), 4);
+        assert_eq!(window(&store), [26, 27, 28, 29]);
+        assert!(store.is_at_end());
+    }
+
+    #[test]
+    fn paged_messages_keep_their_request() {
+        let mut store = Store::new(None, 2);
+        for i in 0..4 {
+            let mut msg = MessageRecord::new(MessageKind::Received, i.to_string());
+            msg.response_to = Some(i * 10);
+            store.push(msg).unwrap();
+        }
+        store.page_up().unwrap();
+        assert_eq!(window(&store), [1, 2]);
+        assert_eq!(store[1].response_to, Some(10));
+    }
+
+    #[test]
+    fn push_while_paged_away_keeps_the_window() {
+        let mut store = filled(None, 4, 10);
+        store.page_up().unwrap();
+        store
+            .push(MessageRecord::new(MessageKind::Sent, "10"))
+            .unwrap();
+        assert_eq!(store.total(), 11);
+        assert_eq!(window(&store), [4, 5, 6, 7]);
+        assert!(!store.is_at_end());
+
+        store.page_end().unwrap();
+        assert_eq!(window(&store), [7, 8, 9, 10]);
+
+        // following the newest messages again
+        store
+            .push(MessageRecord::new(MessageKind::Sent, "11"))
+            .unwrap();
+        assert_eq!(window(&store), [8, 9, 10, 11]);
+    }
+}
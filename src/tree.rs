@@ -0,0 +1,662 @@
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+
+/// The deepest nesting parsed, so the recursive parser cannot overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+/// A node of a pretty-printed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    /// The field name or map key the node is stored under.
+    key: Option<String>,
+    value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Leaf(String),
+    /// An object, array, map, struct or enum variant.
+    Group {
+        /// The struct or variant name in front of the brackets.
+        name: Option<String>,
+        open: char,
+        close: char,
+        children: Vec<Node>,
+        expanded: bool,
+    },
+}
+
+/// A visible line of the tree.
+struct Row {
+    /// The child indices leading from the root to the node.
+    path: Vec<usize>,
+}
+
+/// An interactive tree of a value in JSON, RON or `{:#?}` notation.
+///
+/// The text is parsed loosely, so struct and variant names survive unlike with
+/// `serde_json::Value` or `ron::Value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Tree {
+    root: Node,
+    /// The index of the focused row.
+    focus: usize,
+    /// Whether the text is the `{:#?}` of an untyped value, see [`Tree::untyped`].
+    untyped: bool,
+}
+
+impl Tree {
+    /// Parses a value, returning `None` if it is not well-formed or nested deeper than
+    /// [`MAX_DEPTH`].
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < text.len() {
+            return None;
+        }
+
+        let mut root = Node { key: None, value };
+        // the top level is open from the start
+        if let Value::Group { expanded, .. } = &mut root.value {
+            *expanded = true;
+        }
+
+        Some(Self {
+            root,
+            focus: 0,
+            untyped: false,
+        })
+    }
+
+    /// Marks the text as the `{:#?}` of a `serde_json::Value` or `ron::Value`, whose
+    /// wrappers like `Object` or `Map` are then never taken for an enum variant.
+    pub fn untyped(mut self, untyped: bool) -> Self {
+        self.untyped = untyped;
+        self
+    }
+
+    /// Focuses the next visible node.
+    pub fn down(&mut self, rows: usize) {
+        let len = self.rows().len();
+        self.focus = (self.focus + rows).min(len.saturating_sub(1));
+    }
+
+    /// Focuses the previous visible node.
+    pub fn up(&mut self, rows: usize) {
+        self.focus = self.focus.saturating_sub(rows);
+    }
+
+    /// Expands or collapses the focused node.
+    pub fn toggle(&mut self) {
+        if let Some(Value::Group { expanded, .. }) = self.focused_mut().map(|node| &mut node.value)
+        {
+            *expanded = !*expanded;
+        }
+    }
+
+    /// Expands the focused node, or focuses its first child if it is expanded.
+    pub fn expand(&mut self) {
+        match self.focused_mut().map(|node| &mut node.value) {
+            Some(Value::Group {
+                expanded: expanded @ false,
+                ..
+            }) => *expanded = true,
+            Some(Value::Group { children, .. }) if !children.is_empty() => self.down(1),
+            _ => {}
+        }
+    }
+
+    /// Collapses the focused node, or focuses its parent if it is collapsed.
+    pub fn collapse(&mut self) {
+        if let Some(Value::Group {
+            expanded: expanded @ true,
+            ..
+        }) = self.focused_mut().map(|node| &mut node.value)
+        {
+            *expanded = false;
+            return;
+        }
+
+        let rows = self.rows();
+        let Some(path) = rows.get(self.focus).map(|row| &row.path) else {
+            return;
+        };
+        if let Some(parent) = path.split_last().map(|(_, parent)| parent) {
+            if let Some(idx) = rows.iter().position(|row| row.path == parent) {
+                self.focus = idx;
+            }
+        }
+    }
+
+    /// Returns the jq-like path of the focused node, e.g. `.event.items[2]`.
+    pub fn path(&self) -> String {
+        let rows = self.rows();
+        let Some(row) = rows.get(self.focus) else {
+            return ".".to_string();
+        };
+
+        let mut path = String::new();
+        let mut node = &self.root;
+        for &idx in &row.path {
+            let Value::Group { children, .. } = &node.value else {
+                break;
+            };
+            node = &children[idx];
+            match node.key.as_deref() {
+                Some(key) => {
+                    let name = key
+                        .strip_prefix('"')
+                        .and_then(|key| key.strip_suffix('"'))
+                        .unwrap_or(key);
+                    if is_ident(name) {
+                        path.push('.');
+                        path.push_str(name);
+                    } else {
+                        path.push_str(&format!("[{key}]"));
+                    }
+                }
+                None => path.push_str(&format!("[{idx}]")),
+            }
+        }
+
+        if path.is_empty() {
+            ".".to_string()
+        } else {
+            path
+        }
+    }
+
+    /// Returns the enum variant of the value.
+    ///
+    /// That is the struct or variant name in front of the brackets, or for the untyped
+    /// values of `serde_json` and `ron` the key of an externally tagged map with a single
+    /// entry, or the string of a unit variant.
+    pub fn variant(&self) -> Option<&str> {
+        tag(&self.root, self.untyped).map(|(name, _)| name)
+    }
+
+    /// Returns the enum variant of the value at a jq-like path, see [`Tree::get`].
+    pub fn variant_at(&self, path: &str) -> Option<&str> {
+        tag(self.node_at(path)?, self.untyped).map(|(name, _)| name)
+    }
+
+    /// Returns the scalar at a jq-like path like the ones of [`Tree::path`].
+    ///
+    /// The path is looked up in the fields of the variant first, so `.name` finds the
+    /// name in both `Welcome { name: "bob" }` and `{"Welcome": {"name": "bob"}}`, and
+    /// from the top of the value otherwise.
+    ///
+    /// Newtypes like `Some(1)` or `String("a")` are looked through, other groups return
+    /// their name.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        match &unwrap(self.node_at(path)?).value {
+            Value::Leaf(value) => Some(value),
+            Value::Group { name, .. } => name.as_deref(),
+        }
+    }
+
+    /// Returns the index of the focused line.
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    /// Lays out the visible nodes, one per line.
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        self.rows()
+            .iter()
+            .enumerate()
+            .map(|(idx, row)| {
+                let node = self.node(&row.path);
+                let mut line = "  ".repeat(row.path.len());
+                match &node.value {
+                    Value::Group { children, .. } if children.is_empty() => line.push_str("  "),
+                    Value::Group { expanded: true, .. } => line.push_str("▾ "),
+                    Value::Group { .. } => line.push_str("▸ "),
+                    Value::Leaf(_) => line.push_str("  "),
+                }
+                if let Some(key) = &node.key {
+                    line.push_str(key);
+                    line.push_str(": ");
+                } else if !row.path.is_empty() {
+                    line.push_str(&format!("[{}]: ", row.path[row.path.len() - 1]));
+                }
+                match &node.value {
+                    Value::Leaf(value) => line.push_str(value),
+                    Value::Group {
+                        name,
+                        open,
+                        close,
+                        children,
+                        expanded,
+                    } => {
+                        if let Some(name) = name {
+                            line.push_str(name);
+                            line.push(' ');
+                        }
+                        if children.is_empty() {
+                            line.push(*open);
+                            line.push(*close);
+                        } else if *expanded {
+                            line.push(*open);
+                        } else {
+                            line.push_str(&format!("{open}…{close} {} items", children.len()));
+                        }
+                    }
+                }
+
+                let line = Line::from(line);
+                if idx == self.focus {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the visible nodes in order.
+    fn rows(&self) -> Vec<Row> {
+        fn walk(node: &Node, path: &mut Vec<usize>, rows: &mut Vec<Row>) {
+            rows.push(Row { path: path.clone() });
+            if let Value::Group {
+                children,
+                expanded: true,
+                ..
+            } = &node.value
+            {
+                for (idx, child) in children.iter().enumerate() {
+                    path.push(idx);
+                    walk(child, path, rows);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(&self.root, &mut Vec::new(), &mut rows);
+        rows
+    }
+
+    /// Returns the node at a jq-like path, under the variant first and from the top
+    /// otherwise.
+    fn node_at(&self, path: &str) -> Option<&Node> {
+        tag(&self.root)
+            .and_then(|(_, fields)| lookup(fields, path))
+            .or_else(|| lookup(&self.root, path))
+    }
+
+    fn node(&self, path: &[usize]) -> &Node {
+        path.iter()
+            .fold(&self.root, |node, &idx| match &node.value {
+                Value::Group { children, .. } => &children[idx],
+                Value::Leaf(_) => node,
+            })
+    }
+
+    fn focused_mut(&mut self) -> Option<&mut Node> {
+        let path = self.rows().into_iter().nth(self.focus)?.path;
+        let mut node = &mut self.root;
+        for idx in path {
+            let Value::Group { children, .. } = &mut node.value else {
+                return None;
+            };
+            node = &mut children[idx];
+        }
+
+        Some(node)
+    }
+}
+
+/// A loose parser for the bracketed notations of JSON, RON and `{:#?}`.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// The number of groups open at the position.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    /// Parses a scalar, or a group with an optional name in front.
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let start = self.pos;
+        // scalars may contain colons and spaces, e.g. timestamps and durations
+        while let Some(ch) = self.peek() {
+            match ch {
+                ',' | '(' | '[' | '{' | ')' | ']' | '}' => break,
+                '"' | '\'' => self.string()?,
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+        let atom = self.text[start..self.pos].trim();
+
+        let close = match self.peek() {
+            Some('(') => ')',
+            Some('[') => ']',
+            Some('{') => '}',
+            _ if atom.is_empty() => return None,
+            _ => return Some(Value::Leaf(atom.to_string())),
+        };
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        let open = self.bump()?;
+        self.depth += 1;
+
+        let mut children = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.bump();
+                break;
+            }
+
+            let mut key = self.key();
+            let mut value = self.value()?;
+            self.skip_whitespace();
+            // `ron::Value` prints map keys as values too, e.g. `String("name"): …`
+            if key.is_none() && self.at_colon() {
+                self.bump();
+                key = Some(scalar(&value)?.to_string());
+                value = self.value()?;
+                self.skip_whitespace();
+            }
+            children.push(Node { key, value });
+
+            match self.bump()? {
+                ',' => {}
+                ch if ch == close => break,
+                _ => return None,
+            }
+        }
+        self.depth -= 1;
+
+        Some(Value::Group {
+            name: (!atom.is_empty()).then(|| atom.to_string()),
+            open,
+            close,
+            children,
+            expanded: false,
+        })
+    }
+
+    /// Parses a field name or map key followed by a colon, if there is one.
+    fn key(&mut self) -> Option<String> {
+        let start = self.pos;
+        match self.peek() {
+            Some('"' | '\'') => self.string()?,
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+                {
+                    self.bump();
+                }
+            }
+        }
+        let end = self.pos;
+        self.skip_whitespace();
+
+        if end > start && self.at_colon() {
+            self.bump();
+            Some(self.text[start..end].to_string())
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    /// Returns whether a colon separating a key from its value follows.
+    fn at_colon(&self) -> bool {
+        // `::` separates path segments, not a key from its value
+        self.peek() == Some(':') && !self.text[self.pos..].starts_with("::")
+    }
+
+    /// Skips a quoted string or character literal.
+    fn string(&mut self) -> Option<()> {
+        let quote = self.bump()?;
+        let mut escaped = false;
+        loop {
+            let ch = self.bump()?;
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == quote {
+                return Some(());
+            }
+        }
+    }
+}
+
+/// The names the untyped values of `serde_json` and `ron` print in front of their contents,
+/// which are never an enum variant.
+const UNTYPED: &[&str] = &[
+    "Null", "Bool", "Number", "String", "Array", "Object", "Char", "Map", "Seq", "Bytes", "Option",
+    "Some", "None", "Unit",
+];
+
+/// Returns the enum variant of a node and the node holding its fields.
+///
+/// The [`UNTYPED`] names are only skipped in untyped values, typed ones may well have a
+/// variant called `Map`.
+fn tag(node: &Node, untyped: bool) -> Option<(&str, &Node)> {
+    let is_wrapper = |name: &str| untyped && UNTYPED.contains(&name);
+    let (name, fields) = match &node.value {
+        Value::Group {
+            name: Some(name), ..
+        } if !is_wrapper(name.as_str()) => {
+            return Some((name, newtype(node).unwrap_or(node)));
+        }
+        // untyped wrappers like `String("Ping")` or `Map(Map({…}))`
+        Value::Group { children, .. } => match newtype(node) {
+            Some(inner) => return tag(inner, untyped),
+            None if children.len() == 1 => (children[0].key.as_deref()?, &children[0]),
+            None => return None,
+        },
+        Value::Leaf(value) => (value.as_str(), node),
+    };
+    // a quoted key or string is never a wrapper, even when it reads `"Map"`
+    let wrapper = is_wrapper(name);
+    let name = name.trim_matches('"');
+    let is_variant = name.starts_with(char::is_uppercase) && is_ident(name) && !wrapper;
+
+    is_variant.then_some((name, fields))
+}
+
+/// Follows a jq-like path from the node.
+fn lookup<'a>(node: &'a Node, path: &str) -> Option<&'a Node> {
+    let mut node = node;
+    let mut rest = path;
+    loop {
+        rest = rest.strip_prefix('.').unwrap_or(rest);
+        if rest.is_empty() {
+            return Some(node);
+        }
+        let Value::Group { children, .. } = &unwrap(node).value else {
+            return None;
+        };
+
+        let (segment, after) = match rest.strip_prefix('[') {
+            Some(inner) => inner.split_once(']')?,
+            None => rest.split_at(rest.find(['.', '[']).unwrap_or(rest.len())),
+        };
+        let segment = segment.trim_matches('"');
+        rest = after;
+        node = children
+            .iter()
+            .find(|node| node.key.as_deref().map(|key| key.trim_matches('"')) == Some(segment))
+            .or_else(|| children.get(segment.parse::<usize>().ok()?))?;
+    }
+}
+
+/// Looks through newtypes like `Some(…)`, `String(…)` or `Map(…)` to what they wrap.
+fn unwrap(node: &Node) -> &Node {
+    let mut node = node;
+    while let Some(inner) = newtype(node) {
+        node = inner;
+    }
+
+    node
+}
+
+/// Returns what a newtype wraps, if the node is one.
+fn newtype(node: &Node) -> Option<&Node> {
+    match &node.value {
+        Value::Group {
+            open: '(',
+            children,
+            ..
+        } => match children.as_slice() {
+            [child] if child.key.is_none() => Some(child),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the scalar a newtype wraps, like `"name"` for `String("name")`.
+fn scalar(value: &Value) -> Option<&str> {
+    match value {
+        Value::Leaf(value) => Some(value),
+        Value::Group {
+            open: '(',
+            children,
+            ..
+        } => match children.as_slice() {
+            [child] if child.key.is_none() => scalar(&child.value),
+            _ => None,
+        },
+        Value::Group { .. } => None,
+    }
+}
+
+/// Returns whether the key can be written as `.key` in a path.
+fn is_ident(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && key.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Serialize)]
+    enum Event {
+        Welcome {
+            name: String,
+            items: Vec<u32>,
+            room: Option<String>,
+        },
+    }
+
+    fn welcome() -> Event {
+        Event::Welcome {
+            name: "bob".to_string(),
+            items: vec![1, 2],
+            room: Some("lobby: main".to_string()),
+        }
+    }
+
+    fn assert_welcome(tree: &Tree) {
+        assert_eq!(tree.variant(), Some("Welcome"));
+        assert_eq!(tree.get(".name"), Some(r#""bob""#));
+        assert_eq!(tree.get(".items[1]"), Some("2"));
+        assert_eq!(tree.get(".room"), Some(r#""lobby: main""#));
+        assert_eq!(tree.get(".missing"), None);
+    }
+
+    #[test]
+    fn debug_round_trip() {
+        let tree = Tree::parse(&format!("{:#?}", welcome())).unwrap();
+        assert_welcome(&tree);
+        assert_eq!(tree.get("."), Some("Welcome"));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = serde_json::to_string_pretty(&welcome()).unwrap();
+        assert_welcome(&Tree::parse(&json).unwrap());
+
+        // the `{:#?}` of `serde_json::Value` as recorded in `decoded`
+        let value = serde_json::to_value(welcome()).unwrap();
+        let tree = Tree::parse(&format!("{value:#?}")).unwrap().untyped(true);
+        assert_welcome(&tree);
+        assert_eq!(tree.get(".Welcome.name"), Some(r#""bob""#));
+    }
+
+    #[cfg(any(feature = "in-ron", feature = "out-ron"))]
+    #[test]
+    fn ron_round_trip() {
+        let ron = ron::ser::to_string_pretty(&welcome(), Default::default()).unwrap();
+        assert_welcome(&Tree::parse(&ron).unwrap());
+
+        let value: ron::Value = ron::from_str(&ron).unwrap();
+        let tree = Tree::parse(&format!("{value:#?}")).unwrap();
+        assert_eq!(tree.get(".name"), Some(r#""bob""#));
+        assert_eq!(tree.get(".items[0]"), Some("1"));
+    }
+
+    #[test]
+    fn malformed_text_is_rejected() {
+        assert_eq!(Tree::parse(""), None);
+        assert_eq!(Tree::parse(r#"{"name": "bob""#), None);
+        assert_eq!(Tree::parse(r#"{"name": "bob"} }"#), None);
+        assert_eq!(Tree::parse(r#"{"name": "bob}"#), None);
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let nested = |depth| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Tree::parse(&nested(MAX_DEPTH)).is_some());
+        assert_eq!(Tree::parse(&nested(MAX_DEPTH + 1)), None);
+        assert_eq!(Tree::parse(&"[".repeat(100_000)), None);
+    }
+
+    #[test]
+    fn paths_follow_the_focus() {
+        let mut tree = Tree::parse(&format!("{:#?}", welcome())).unwrap();
+        assert_eq!(tree.path(), ".");
+        assert_eq!(tree.lines().len(), 4);
+
+        tree.down(2);
+        assert_eq!(tree.path(), ".items");
+        tree.expand();
+        assert_eq!(tree.lines().len(), 6);
+        tree.expand();
+        assert_eq!(tree.path(), ".items[0]");
+
+        tree.collapse();
+        assert_eq!(tree.path(), ".items");
+        tree.collapse();
+        assert_eq!(tree.lines().len(), 4);
+        tree.down(10);
+        assert_eq!(tree.focus(), 3);
+        assert_eq!(tree.path(), ".room");
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
 	ops::{Deref, DerefMut},
+	sync::atomic::{AtomicBool, Ordering},
 	time::Duration,
 };
 
@@ -9,7 +10,10 @@ use futures::{FutureExt, StreamExt};
 use ratatui::backend::CrosstermBackend as Backend;
 use ratatui::crossterm::{
 	cursor,
-	event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent},
+	event::{
+		DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event as CrosstermEvent, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+		MouseEvent, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+	},
 	terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use serde::{Deserialize, Serialize};
@@ -19,6 +23,9 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+/// Whether keyboard enhancement flags were pushed and not popped yet, for [`restore`].
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
 	Init,
@@ -45,6 +52,9 @@ pub struct Tui {
 	pub tick_rate: f64,
 	pub mouse: bool,
 	pub paste: bool,
+	pub keyboard_enhancement: bool,
+	/// Whether the terminal is set up, so [`Tui::exit`] only restores it once.
+	entered: bool,
 }
 
 impl Tui {
@@ -57,6 +67,8 @@ impl Tui {
 		let task = tokio::spawn(async {});
 		let mouse = false;
 		let paste = false;
+		let keyboard_enhancement = false;
+		let entered = false;
 		Ok(Self {
 			terminal,
 			task,
@@ -67,6 +79,8 @@ impl Tui {
 			tick_rate,
 			mouse,
 			paste,
+			keyboard_enhancement,
+			entered,
 		})
 	}
 
@@ -101,12 +115,14 @@ impl Tui {
 			let mut reader = crossterm::event::EventStream::new();
 			let mut tick_interval = tokio::time::interval(tick_delay);
 			let mut render_interval = tokio::time::interval(render_delay);
-			_event_tx.send(Event::Init).unwrap();
+			if _event_tx.send(Event::Init).is_err() {
+				return;
+			}
 			loop {
 				let tick_delay = tick_interval.tick();
 				let render_delay = render_interval.tick();
 				let crossterm_event = reader.next().fuse();
-				tokio::select! {
+				let event = tokio::select! {
 				  _ = _cancellation_token.cancelled() => {
 					break;
 				  }
@@ -115,39 +131,32 @@ impl Tui {
 					  Some(Ok(evt)) => {
 						match evt {
 						  CrosstermEvent::Key(key) => {
-							if key.kind == KeyEventKind::Press {
-							  _event_tx.send(Event::Key(key)).unwrap();
+							if key.kind != KeyEventKind::Press {
+							  continue;
 							}
+							Event::Key(key)
 						  },
-						  CrosstermEvent::Mouse(mouse) => {
-							_event_tx.send(Event::Mouse(mouse)).unwrap();
-						  },
-						  CrosstermEvent::Resize(x, y) => {
-							_event_tx.send(Event::Resize(x, y)).unwrap();
-						  },
-						  CrosstermEvent::FocusLost => {
-							_event_tx.send(Event::FocusLost).unwrap();
-						  },
-						  CrosstermEvent::FocusGained => {
-							_event_tx.send(Event::FocusGained).unwrap();
-						  },
-						  CrosstermEvent::Paste(s) => {
-							_event_tx.send(Event::Paste(s)).unwrap();
-						  },
+						  CrosstermEvent::Mouse(mouse) => Event::Mouse(mouse),
+						  CrosstermEvent::Resize(x, y) => Event::Resize(x, y),
+						  CrosstermEvent::FocusLost => Event::FocusLost,
+						  CrosstermEvent::FocusGained => Event::FocusGained,
+						  CrosstermEvent::Paste(s) => Event::Paste(s),
 						}
 					  }
-					  Some(Err(_)) => {
-						_event_tx.send(Event::Error).unwrap();
-					  }
-					  None => {},
+					  Some(Err(_)) => Event::Error,
+					  // the terminal is gone, so there is nothing left to read
+					  None => {
+						let _ = _event_tx.send(Event::Closed);
+						break;
+					  },
 					}
 				  },
-				  _ = tick_delay => {
-					  _event_tx.send(Event::Tick).unwrap();
-				  },
-				  _ = render_delay => {
-					  _event_tx.send(Event::Render).unwrap();
-				  },
+				  _ = tick_delay => Event::Tick,
+				  _ = render_delay => Event::Render,
+				};
+				// the receiver only goes away with the app
+				if _event_tx.send(event).is_err() {
+					break;
 				}
 			}
 		});
@@ -170,6 +179,7 @@ impl Tui {
 	}
 
 	pub fn enter(&mut self) -> Result<()> {
+		self.entered = true;
 		crossterm::terminal::enable_raw_mode()?;
 		crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
 		if self.mouse {
@@ -178,14 +188,29 @@ impl Tui {
 		if self.paste {
 			crossterm::execute!(std::io::stderr(), EnableBracketedPaste)?;
 		}
+		// lets modified keys such as Shift+Enter through on terminals that support it
+		if crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false) {
+			crossterm::execute!(std::io::stderr(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+			self.keyboard_enhancement = true;
+			KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+		}
 		self.start();
 		Ok(())
 	}
 
+	/// Restores the terminal. Does nothing if it was not set up or is already restored.
 	pub fn exit(&mut self) -> Result<()> {
-		self.stop()?;
+		if !self.entered {
+			return Ok(());
+		}
+		// the terminal is restored even if the event task is stuck
+		let stopped = self.stop();
 		if crossterm::terminal::is_raw_mode_enabled()? {
 			self.flush()?;
+			if std::mem::take(&mut self.keyboard_enhancement) {
+				KEYBOARD_ENHANCED.store(false, Ordering::Relaxed);
+				crossterm::execute!(std::io::stderr(), PopKeyboardEnhancementFlags)?;
+			}
 			if self.paste {
 				crossterm::execute!(std::io::stderr(), DisableBracketedPaste)?;
 			}
@@ -195,7 +220,8 @@ impl Tui {
 			crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
 			crossterm::terminal::disable_raw_mode()?;
 		}
-		Ok(())
+		self.entered = false;
+		stopped
 	}
 
 	pub fn cancel(&self) {
@@ -235,6 +261,30 @@ impl DerefMut for Tui {
 
 impl Drop for Tui {
 	fn drop(&mut self) {
-		self.exit().unwrap();
+		// callers wanting the error call `exit` first, printing it here could land in raw mode
+		let _ = self.exit();
+	}
+}
+
+/// Leaves the alternate screen and raw mode without a [`Tui`] at hand, e.g. from a panic hook.
+///
+/// Does nothing if raw mode is off, so output without a terminal UI is left alone.
+pub(crate) fn restore() -> Result<()> {
+	if !crossterm::terminal::is_raw_mode_enabled()? {
+		return Ok(());
+	}
+	// popping flags that were never pushed would pop the ones of the shell instead
+	if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+		crossterm::execute!(std::io::stderr(), PopKeyboardEnhancementFlags)?;
 	}
+	// undoing a mode that was never turned on is harmless
+	crossterm::execute!(
+		std::io::stderr(),
+		DisableBracketedPaste,
+		DisableMouseCapture,
+		LeaveAlternateScreen,
+		cursor::Show
+	)?;
+	crossterm::terminal::disable_raw_mode()?;
+	Ok(())
 }